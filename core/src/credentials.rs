@@ -0,0 +1,1183 @@
+use std::collections::BTreeMap;
+
+use base64::prelude::*;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    normalise_country, normalise_region, ConflictZones, Coordinate, CustomClaims, Err, Issuer,
+    RegisteredClaims, Validator, ZoneTree,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum CredentialError {
+    #[error("Missing claim '{0}'")]
+    MissingClaim(&'static str),
+
+    #[error("Invalid claim '{key}': {reason}")]
+    InvalidClaim { key: &'static str, reason: String },
+
+    #[error("Invalid date in claim '{key}': {reason}")]
+    InvalidDate { key: &'static str, reason: String },
+
+    #[error("Reserved claim '{0}'")]
+    ReservedClaim(String),
+}
+
+/// Licence issued by a national mining authority to a mining company.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MiningLicence {
+    pub issuer_id: String,
+    pub subject_id: String,
+    /// Public JWK of the mining company, used to verify its product passports
+    pub subject_pk: String,
+    pub issue_date: DateTime<Utc>,
+    pub expiry_date: DateTime<Utc>,
+    pub country_of_operation: String,
+    pub region_of_operation: String,
+    /// Positions of the licensed mine sites; licences without the claim have none
+    pub sites: Vec<Coordinate>,
+    /// Position of the licence in its authority's [`LicenceStatusList`], if
+    /// it can be revoked
+    pub status_index: Option<u64>,
+}
+
+/// Passport issued by a mining company for a single shipment.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProductPassport {
+    pub shipment_id: String,
+    pub product: String,
+    pub issue_date: DateTime<Utc>,
+    /// Licences a blended shipment was sourced under; passports without the
+    /// claim come from a single licence
+    pub sources: Vec<SourceShare>,
+    /// Declared mass of the shipment, which the sources of a blended shipment
+    /// must add up to
+    pub declared_mass_kg: Option<u64>,
+    /// Further claims about the shipment, such as `recycled_content_percent`,
+    /// signed alongside the others; keys must not be those of the fields above
+    /// or of the claims the guest commits (see [`ProductPassport::validate`])
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// Keys of the claims [`ProductPassport`] reads into its own fields.
+pub const PASSPORT_CLAIMS: [&str; 5] = [
+    "shipment_id",
+    "product",
+    "issue_date",
+    "sources",
+    "declared_mass_kg",
+];
+
+/// Keys the guest commits to the journal alongside the disclosed passport
+/// claims.  Predicates and claim commitments are committed under
+/// `predicate:<predicate>` and `<key>_commitment`.
+pub const JOURNAL_CLAIMS: [&str; 22] = [
+    "disclosure",
+    "country_of_operation",
+    "licence_valid_when_signing_product_passport",
+    "passport_signing_date",
+    "sites_outside_conflict_zones",
+    "strict_mode",
+    "conflict_zones_digest",
+    "conflict_zones_root",
+    "conflict_zones_version",
+    "conflict_zones_publication_date",
+    "source_count",
+    "non_conflict_mass_percent",
+    "licence_status_checked",
+    "licence_status_publication_date",
+    "recall_checked",
+    "recall_list_publication_date",
+    "nullifier",
+    "nullifier_context",
+    "final_holder_id",
+    "final_holder_fingerprint",
+    "final_holder_pk",
+    "custody_transfer_date",
+];
+
+/// The mass of a blended shipment sourced under one mining licence.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SourceShare {
+    /// [`crate::token_digest`] of the licence token
+    pub licence_digest: String,
+    pub mass_kg: u64,
+}
+
+fn find_claim<'a>(claims: &'a CustomClaims, key: &'static str) -> Result<&'a str, CredentialError> {
+    claims
+        .claims
+        .iter()
+        .find(|claim| claim.key == key)
+        .map(|claim| claim.value.as_str())
+        .ok_or(CredentialError::MissingClaim(key))
+}
+
+fn find_date_claim(
+    claims: &CustomClaims,
+    key: &'static str,
+) -> Result<DateTime<Utc>, CredentialError> {
+    find_claim(claims, key)?
+        .parse::<DateTime<Utc>>()
+        .map_err(|err| CredentialError::InvalidDate {
+            key,
+            reason: err.to_string(),
+        })
+}
+
+fn find_number_claim<T: std::str::FromStr>(
+    claims: &CustomClaims,
+    key: &'static str,
+) -> Result<T, CredentialError>
+where
+    T::Err: std::fmt::Display,
+{
+    find_claim(claims, key)?
+        .parse::<T>()
+        .map_err(|err| CredentialError::InvalidClaim {
+            key,
+            reason: err.to_string(),
+        })
+}
+
+/// Formats dates as e.g. `2025-01-01T00:00:00Z`, matching the claims files.
+pub fn format_date(date: &DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+impl TryFrom<&CustomClaims> for MiningLicence {
+    type Error = CredentialError;
+
+    fn try_from(claims: &CustomClaims) -> Result<Self, Self::Error> {
+        Self {
+            issuer_id: find_claim(claims, "issuer_id")?.to_string(),
+            subject_id: find_claim(claims, "subject_id")?.to_string(),
+            subject_pk: find_claim(claims, "subject_pk")?.to_string(),
+            issue_date: find_date_claim(claims, "issue_date")?,
+            expiry_date: find_date_claim(claims, "expiry_date")?,
+            country_of_operation: find_claim(claims, "country_of_operation")?.to_string(),
+            region_of_operation: find_claim(claims, "region_of_operation")?.to_string(),
+            sites: match find_claim(claims, "site_coordinates") {
+                Ok(sites) => {
+                    serde_json::from_str(sites).map_err(|err| CredentialError::InvalidClaim {
+                        key: "site_coordinates",
+                        reason: err.to_string(),
+                    })?
+                }
+                Err(_) => Vec::new(),
+            },
+            status_index: match find_claim(claims, "status_list_index") {
+                Ok(_) => Some(find_number_claim(claims, "status_list_index")?),
+                Err(_) => None,
+            },
+        }
+        .normalised()
+    }
+}
+
+impl From<&MiningLicence> for CustomClaims {
+    fn from(licence: &MiningLicence) -> Self {
+        let mut claims = CustomClaims::new();
+        claims.add("issuer_id".to_string(), licence.issuer_id.clone());
+        claims.add("subject_id".to_string(), licence.subject_id.clone());
+        claims.add("subject_pk".to_string(), licence.subject_pk.clone());
+        claims.add("issue_date".to_string(), format_date(&licence.issue_date));
+        claims.add("expiry_date".to_string(), format_date(&licence.expiry_date));
+        claims.add(
+            "country_of_operation".to_string(),
+            licence.country_of_operation.clone(),
+        );
+        claims.add(
+            "region_of_operation".to_string(),
+            licence.region_of_operation.clone(),
+        );
+        if !licence.sites.is_empty() {
+            claims.add(
+                "site_coordinates".to_string(),
+                serde_json::to_string(&licence.sites).expect("Could not serialise sites"),
+            );
+        }
+        if let Some(status_index) = licence.status_index {
+            claims.add("status_list_index".to_string(), status_index.to_string());
+        }
+        claims
+    }
+}
+
+impl MiningLicence {
+    /// Returns the licence with its country of operation as an ISO 3166-1
+    /// alpha-2 code and its region as an ISO 3166-2 code.  Licences are
+    /// normalised when signed and verified, so the guest only compares codes.
+    pub fn normalised(&self) -> Result<Self, CredentialError> {
+        let country = normalise_country(&self.country_of_operation).map_err(|err| {
+            CredentialError::InvalidClaim {
+                key: "country_of_operation",
+                reason: err.to_string(),
+            }
+        })?;
+        let region = normalise_region(country, &self.region_of_operation).map_err(|err| {
+            CredentialError::InvalidClaim {
+                key: "region_of_operation",
+                reason: err.to_string(),
+            }
+        })?;
+        Ok(Self {
+            country_of_operation: country.to_string(),
+            region_of_operation: region.to_string(),
+            ..self.clone()
+        })
+    }
+
+    /// Registered claims matching the licence: `nbf`/`exp` span its validity window.
+    pub fn registered_claims(&self) -> RegisteredClaims {
+        RegisteredClaims::new()
+            .not_before(self.issue_date)
+            .expires_at(self.expiry_date)
+            .issuer(self.issuer_id.clone())
+            .subject(self.subject_id.clone())
+    }
+
+    /// Signs the normalised licence, rejecting unknown country and region codes.
+    pub fn sign(&self, issuer: &Issuer) -> Result<String, Err> {
+        let licence = self.normalised()?;
+        issuer
+            .generate_token_with_registered_claims(&(&licence).into(), &licence.registered_claims())
+    }
+
+    pub fn verify(token: &str, validator: &Validator) -> Result<Self, Err> {
+        let valid_token = validator.validate_token_integrity(token)?;
+        Ok(Self::try_from(&valid_token.claims().custom.custom)?)
+    }
+
+    /// Returns a validator for tokens signed by the licence holder.
+    pub fn subject_validator(&self) -> Result<Validator, Err> {
+        self.subject_pk.parse::<Validator>()
+    }
+}
+
+impl TryFrom<&CustomClaims> for ProductPassport {
+    type Error = CredentialError;
+
+    fn try_from(claims: &CustomClaims) -> Result<Self, Self::Error> {
+        let sources: Vec<SourceShare> = match find_claim(claims, "sources") {
+            Ok(sources) => {
+                serde_json::from_str(sources).map_err(|err| CredentialError::InvalidClaim {
+                    key: "sources",
+                    reason: err.to_string(),
+                })?
+            }
+            Err(_) => Vec::new(),
+        };
+        // The sources are only meaningful against a declared total
+        let declared_mass_kg = match find_claim(claims, "declared_mass_kg") {
+            Ok(_) => Some(find_number_claim(claims, "declared_mass_kg")?),
+            Err(_) if sources.is_empty() => None,
+            Err(err) => return Err(err),
+        };
+        let passport = Self {
+            shipment_id: find_claim(claims, "shipment_id")?.to_string(),
+            product: find_claim(claims, "product")?.to_string(),
+            issue_date: find_date_claim(claims, "issue_date")?,
+            sources,
+            declared_mass_kg,
+            attributes: claims
+                .claims
+                .iter()
+                .filter(|claim| !PASSPORT_CLAIMS.contains(&claim.key.as_str()))
+                .map(|claim| (claim.key.clone(), claim.value.clone()))
+                .collect(),
+        };
+        passport.validate()?;
+        Ok(passport)
+    }
+}
+
+impl From<&ProductPassport> for CustomClaims {
+    fn from(passport: &ProductPassport) -> Self {
+        let mut claims = CustomClaims::new();
+        claims.add("shipment_id".to_string(), passport.shipment_id.clone());
+        claims.add("product".to_string(), passport.product.clone());
+        claims.add("issue_date".to_string(), format_date(&passport.issue_date));
+        if !passport.sources.is_empty() {
+            claims.add(
+                "sources".to_string(),
+                serde_json::to_string(&passport.sources).expect("Could not serialise sources"),
+            );
+        }
+        if let Some(declared_mass_kg) = passport.declared_mass_kg {
+            claims.add("declared_mass_kg".to_string(), declared_mass_kg.to_string());
+        }
+        for (key, value) in passport.attributes.iter() {
+            claims.add(key.clone(), value.clone());
+        }
+        claims
+    }
+}
+
+impl ProductPassport {
+    /// Registered claims matching the passport: `iat` is its issue date and `jti` the shipment ID.
+    pub fn registered_claims(&self) -> RegisteredClaims {
+        RegisteredClaims::new()
+            .issued_at(self.issue_date)
+            .jwt_id(self.shipment_id.clone())
+    }
+
+    /// Checks that no attribute takes the key of a passport field or of a
+    /// claim the guest commits, which verifiers reading the first claim under
+    /// a key would otherwise take from the passport.
+    pub fn validate(&self) -> Result<(), CredentialError> {
+        let reserved = |key: &str| {
+            PASSPORT_CLAIMS.contains(&key)
+                || JOURNAL_CLAIMS.contains(&key)
+                || key.starts_with("predicate:")
+                || key.ends_with("_commitment")
+        };
+        match self.attributes.keys().find(|key| reserved(key)) {
+            Some(key) => Err(CredentialError::ReservedClaim(key.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Signs the passport, rejecting attributes under reserved keys.
+    pub fn sign(&self, issuer: &Issuer) -> Result<String, Err> {
+        self.validate()?;
+        issuer.generate_token_with_registered_claims(&self.into(), &self.registered_claims())
+    }
+
+    pub fn verify(token: &str, validator: &Validator) -> Result<Self, Err> {
+        let valid_token = validator.validate_token_integrity(token)?;
+        Ok(Self::try_from(&valid_token.claims().custom.custom)?)
+    }
+}
+
+/// Transfer of custody of a shipment, signed by the current holder and naming the next one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CustodyTransfer {
+    pub shipment_id: String,
+    pub issuer_id: String,
+    pub subject_id: String,
+    /// Public JWK of the next holder, used to verify the following transfer
+    pub subject_pk: String,
+    pub transfer_date: DateTime<Utc>,
+}
+
+impl TryFrom<&CustomClaims> for CustodyTransfer {
+    type Error = CredentialError;
+
+    fn try_from(claims: &CustomClaims) -> Result<Self, Self::Error> {
+        Ok(Self {
+            shipment_id: find_claim(claims, "shipment_id")?.to_string(),
+            issuer_id: find_claim(claims, "issuer_id")?.to_string(),
+            subject_id: find_claim(claims, "subject_id")?.to_string(),
+            subject_pk: find_claim(claims, "subject_pk")?.to_string(),
+            transfer_date: find_date_claim(claims, "transfer_date")?,
+        })
+    }
+}
+
+impl From<&CustodyTransfer> for CustomClaims {
+    fn from(transfer: &CustodyTransfer) -> Self {
+        let mut claims = CustomClaims::new();
+        claims.add("shipment_id".to_string(), transfer.shipment_id.clone());
+        claims.add("issuer_id".to_string(), transfer.issuer_id.clone());
+        claims.add("subject_id".to_string(), transfer.subject_id.clone());
+        claims.add("subject_pk".to_string(), transfer.subject_pk.clone());
+        claims.add(
+            "transfer_date".to_string(),
+            format_date(&transfer.transfer_date),
+        );
+        claims
+    }
+}
+
+impl CustodyTransfer {
+    /// Registered claims matching the transfer: `iat` is the transfer date.
+    pub fn registered_claims(&self) -> RegisteredClaims {
+        RegisteredClaims::new()
+            .issued_at(self.transfer_date)
+            .issuer(self.issuer_id.clone())
+            .subject(self.subject_id.clone())
+    }
+
+    pub fn sign(&self, issuer: &Issuer) -> Result<String, Err> {
+        issuer.generate_token_with_registered_claims(&self.into(), &self.registered_claims())
+    }
+
+    pub fn verify(token: &str, validator: &Validator) -> Result<Self, Err> {
+        let valid_token = validator.validate_token_integrity(token)?;
+        Ok(Self::try_from(&valid_token.claims().custom.custom)?)
+    }
+
+    /// Returns a validator for tokens signed by the next holder.
+    pub fn subject_validator(&self) -> Result<Validator, Err> {
+        self.subject_pk.parse::<Validator>()
+    }
+}
+
+/// Product passports withdrawn by the mining company that issued them, e.g.
+/// for a mis-declared shipment or a quality recall.  Each list supersedes the
+/// previous one, so it names every passport recalled so far.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecallList {
+    pub issuer_id: String,
+    pub publication_date: DateTime<Utc>,
+    pub shipment_ids: Vec<String>,
+}
+
+impl TryFrom<&CustomClaims> for RecallList {
+    type Error = CredentialError;
+
+    fn try_from(claims: &CustomClaims) -> Result<Self, Self::Error> {
+        Ok(Self {
+            issuer_id: find_claim(claims, "issuer_id")?.to_string(),
+            publication_date: find_date_claim(claims, "publication_date")?,
+            shipment_ids: serde_json::from_str(find_claim(claims, "recalled_shipments")?).map_err(
+                |err| CredentialError::InvalidClaim {
+                    key: "recalled_shipments",
+                    reason: err.to_string(),
+                },
+            )?,
+        })
+    }
+}
+
+impl From<&RecallList> for CustomClaims {
+    fn from(list: &RecallList) -> Self {
+        let mut claims = CustomClaims::new();
+        claims.add("issuer_id".to_string(), list.issuer_id.clone());
+        claims.add(
+            "publication_date".to_string(),
+            format_date(&list.publication_date),
+        );
+        claims.add(
+            "recalled_shipments".to_string(),
+            serde_json::to_string(&list.shipment_ids).expect("Could not serialise shipment ids"),
+        );
+        claims
+    }
+}
+
+impl RecallList {
+    pub fn is_recalled(&self, shipment_id: &str) -> bool {
+        self.shipment_ids
+            .iter()
+            .any(|recalled| recalled == shipment_id)
+    }
+
+    /// Registered claims matching the list: `iat` is its publication date.
+    pub fn registered_claims(&self) -> RegisteredClaims {
+        RegisteredClaims::new()
+            .issued_at(self.publication_date)
+            .issuer(self.issuer_id.clone())
+    }
+
+    pub fn sign(&self, issuer: &Issuer) -> Result<String, Err> {
+        issuer.generate_token_with_registered_claims(&self.into(), &self.registered_claims())
+    }
+
+    pub fn verify(token: &str, validator: &Validator) -> Result<Self, Err> {
+        let valid_token = validator.validate_token_integrity(token)?;
+        Ok(Self::try_from(&valid_token.claims().custom.custom)?)
+    }
+}
+
+/// Revocation status of the licences issued by a national mining authority,
+/// in the style of a W3C status list: bit `i` of the list, counting from the
+/// most significant bit of the first byte, is set if the licence with
+/// [`MiningLicence::status_index`] `i` has been revoked.  Lists should be
+/// large, so that checking one licence does not single it out.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LicenceStatusList {
+    pub issuer_id: String,
+    pub publication_date: DateTime<Utc>,
+    pub statuses: Vec<u8>,
+}
+
+impl TryFrom<&CustomClaims> for LicenceStatusList {
+    type Error = CredentialError;
+
+    fn try_from(claims: &CustomClaims) -> Result<Self, Self::Error> {
+        Ok(Self {
+            issuer_id: find_claim(claims, "issuer_id")?.to_string(),
+            publication_date: find_date_claim(claims, "publication_date")?,
+            statuses: BASE64_URL_SAFE_NO_PAD
+                .decode(find_claim(claims, "status_list")?)
+                .map_err(|err| CredentialError::InvalidClaim {
+                    key: "status_list",
+                    reason: err.to_string(),
+                })?,
+        })
+    }
+}
+
+impl From<&LicenceStatusList> for CustomClaims {
+    fn from(list: &LicenceStatusList) -> Self {
+        let mut claims = CustomClaims::new();
+        claims.add("issuer_id".to_string(), list.issuer_id.clone());
+        claims.add(
+            "publication_date".to_string(),
+            format_date(&list.publication_date),
+        );
+        claims.add(
+            "status_list".to_string(),
+            BASE64_URL_SAFE_NO_PAD.encode(&list.statuses),
+        );
+        claims
+    }
+}
+
+impl LicenceStatusList {
+    /// A list of `size` licences, none of them revoked.  The size is rounded
+    /// up to a whole number of bytes.
+    pub fn new(issuer_id: String, publication_date: DateTime<Utc>, size: u64) -> Self {
+        Self {
+            issuer_id,
+            publication_date,
+            statuses: vec![0; size.div_ceil(8) as usize],
+        }
+    }
+
+    /// Number of licences the list has room for.
+    pub fn size(&self) -> u64 {
+        self.statuses.len() as u64 * 8
+    }
+
+    fn position(&self, index: u64) -> Result<(usize, u8), CredentialError> {
+        if index >= self.size() {
+            return Err(CredentialError::InvalidClaim {
+                key: "status_list_index",
+                reason: format!("{index} is beyond the {} entries of the list", self.size()),
+            });
+        }
+        Ok(((index / 8) as usize, 0x80 >> (index % 8)))
+    }
+
+    pub fn revoke(&mut self, index: u64) -> Result<(), CredentialError> {
+        let (byte, bit) = self.position(index)?;
+        self.statuses[byte] |= bit;
+        Ok(())
+    }
+
+    pub fn is_revoked(&self, index: u64) -> Result<bool, CredentialError> {
+        let (byte, bit) = self.position(index)?;
+        Ok(self.statuses[byte] & bit != 0)
+    }
+
+    /// Registered claims matching the list: `iat` is its publication date.
+    pub fn registered_claims(&self) -> RegisteredClaims {
+        RegisteredClaims::new()
+            .issued_at(self.publication_date)
+            .issuer(self.issuer_id.clone())
+    }
+
+    pub fn sign(&self, issuer: &Issuer) -> Result<String, Err> {
+        issuer.generate_token_with_registered_claims(&self.into(), &self.registered_claims())
+    }
+
+    pub fn verify(token: &str, validator: &Validator) -> Result<Self, Err> {
+        let valid_token = validator.validate_token_integrity(token)?;
+        Ok(Self::try_from(&valid_token.claims().custom.custom)?)
+    }
+}
+
+/// Conflict zone list published by a designated authority.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConflictZoneList {
+    pub publisher_id: String,
+    pub publication_date: DateTime<Utc>,
+    pub conflict_zones: ConflictZones,
+}
+
+impl TryFrom<&CustomClaims> for ConflictZoneList {
+    type Error = CredentialError;
+
+    fn try_from(claims: &CustomClaims) -> Result<Self, Self::Error> {
+        Self {
+            publisher_id: find_claim(claims, "publisher_id")?.to_string(),
+            publication_date: find_date_claim(claims, "publication_date")?,
+            conflict_zones: serde_json::from_str(find_claim(claims, "conflict_zones")?).map_err(
+                |err| CredentialError::InvalidClaim {
+                    key: "conflict_zones",
+                    reason: err.to_string(),
+                },
+            )?,
+        }
+        .normalised()
+    }
+}
+
+impl From<&ConflictZoneList> for CustomClaims {
+    fn from(list: &ConflictZoneList) -> Self {
+        let mut claims = CustomClaims::new();
+        claims.add("publisher_id".to_string(), list.publisher_id.clone());
+        claims.add(
+            "publication_date".to_string(),
+            format_date(&list.publication_date),
+        );
+        claims.add(
+            "conflict_zones".to_string(),
+            serde_json::to_string(&list.conflict_zones)
+                .expect("Could not serialise conflict zones"),
+        );
+        claims
+    }
+}
+
+impl ConflictZoneList {
+    /// Returns the list with every zone normalised to ISO 3166 codes.
+    pub fn normalised(&self) -> Result<Self, CredentialError> {
+        Ok(Self {
+            conflict_zones: self.conflict_zones.normalised().map_err(|err| {
+                CredentialError::InvalidClaim {
+                    key: "conflict_zones",
+                    reason: err.to_string(),
+                }
+            })?,
+            ..self.clone()
+        })
+    }
+
+    /// Registered claims matching the list: `iat` is its publication date.
+    pub fn registered_claims(&self) -> RegisteredClaims {
+        RegisteredClaims::new()
+            .issued_at(self.publication_date)
+            .issuer(self.publisher_id.clone())
+    }
+
+    /// Signs the normalised list, rejecting unknown country and region codes.
+    pub fn sign(&self, issuer: &Issuer) -> Result<String, Err> {
+        let list = self.normalised()?;
+        issuer.generate_token_with_registered_claims(&(&list).into(), &list.registered_claims())
+    }
+
+    pub fn verify(token: &str, validator: &Validator) -> Result<Self, Err> {
+        let valid_token = validator.validate_token_integrity(token)?;
+        Ok(Self::try_from(&valid_token.claims().custom.custom)?)
+    }
+}
+
+/// Merkle root of a conflict zone list published by a designated authority,
+/// for lists too large for the guest to read in full.  The list itself is
+/// published alongside, so that provers can build witnesses from it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConflictZoneRoot {
+    pub publisher_id: String,
+    pub publication_date: DateTime<Utc>,
+    pub version: u32,
+    /// Root of the [`ZoneTree`] over the normalised list
+    pub root: String,
+    /// Number of zones in the tree, which fixes where each leaf lies
+    pub zone_count: usize,
+}
+
+impl TryFrom<&CustomClaims> for ConflictZoneRoot {
+    type Error = CredentialError;
+
+    fn try_from(claims: &CustomClaims) -> Result<Self, Self::Error> {
+        Ok(Self {
+            publisher_id: find_claim(claims, "publisher_id")?.to_string(),
+            publication_date: find_date_claim(claims, "publication_date")?,
+            version: find_number_claim(claims, "conflict_zones_version")?,
+            root: find_claim(claims, "conflict_zones_root")?.to_string(),
+            zone_count: find_number_claim(claims, "conflict_zones_count")?,
+        })
+    }
+}
+
+impl From<&ConflictZoneRoot> for CustomClaims {
+    fn from(root: &ConflictZoneRoot) -> Self {
+        let mut claims = CustomClaims::new();
+        claims.add("publisher_id".to_string(), root.publisher_id.clone());
+        claims.add(
+            "publication_date".to_string(),
+            format_date(&root.publication_date),
+        );
+        claims.add(
+            "conflict_zones_version".to_string(),
+            root.version.to_string(),
+        );
+        claims.add("conflict_zones_root".to_string(), root.root.clone());
+        claims.add(
+            "conflict_zones_count".to_string(),
+            root.zone_count.to_string(),
+        );
+        claims
+    }
+}
+
+impl ConflictZoneRoot {
+    /// Builds the tree over a list after normalising it, rejecting zones with
+    /// areas and regions listed twice.
+    pub fn from_list(list: &ConflictZoneList) -> Result<Self, CredentialError> {
+        let list = list.normalised()?;
+        let tree =
+            ZoneTree::build(&list.conflict_zones).map_err(|err| CredentialError::InvalidClaim {
+                key: "conflict_zones",
+                reason: err.to_string(),
+            })?;
+        Ok(Self {
+            publisher_id: list.publisher_id,
+            publication_date: list.publication_date,
+            version: list.conflict_zones.version,
+            root: tree.root(),
+            zone_count: tree.len(),
+        })
+    }
+
+    /// Registered claims matching the root: `iat` is its publication date.
+    pub fn registered_claims(&self) -> RegisteredClaims {
+        RegisteredClaims::new()
+            .issued_at(self.publication_date)
+            .issuer(self.publisher_id.clone())
+    }
+
+    pub fn sign(&self, issuer: &Issuer) -> Result<String, Err> {
+        issuer.generate_token_with_registered_claims(&self.into(), &self.registered_claims())
+    }
+
+    pub fn verify(token: &str, validator: &Validator) -> Result<Self, Err> {
+        let valid_token = validator.validate_token_integrity(token)?;
+        Ok(Self::try_from(&valid_token.claims().custom.custom)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const ISSUER_SECRET_KEY: &str = r#"
+    {
+      "kty": "EC",
+      "crv": "P-256",
+      "alg": "ES256",
+      "x": "nqqYqxl4GS_nmvRsZBrGOrMHXXRby1MQs5oHYlAB4NE",
+      "y": "JN2j0rJ2x-DooKUPJbSpXRxyYa1NXvEdrXeWWwBTOP8",
+      "d": "wdyZdBz6w0pYtzrP1Oq0GPaMBRF7uUDQ_xEnBtsp8lA"
+    }
+    "#;
+
+    const ISSUER_PUBLIC_KEY: &str = r#"
+    {
+      "kty": "EC",
+      "crv": "P-256",
+      "alg": "ES256",
+      "x": "nqqYqxl4GS_nmvRsZBrGOrMHXXRby1MQs5oHYlAB4NE",
+      "y": "JN2j0rJ2x-DooKUPJbSpXRxyYa1NXvEdrXeWWwBTOP8"
+    }
+    "#;
+
+    const SUBJECT_SECRET_KEY: &str = r#"
+    {
+      "kty": "OKP",
+      "crv": "Ed25519",
+      "alg": "EdDSA",
+      "x": "OasNl_txIwm8IWTT2jWNe8H3MpmeHcvl20NgTxqeGvE",
+      "d": "vJ0WIE5v157cRyrmt6a8Q2PqmD9m6BI3OwxpSls9mjg"
+    }
+    "#;
+
+    const SUBJECT_PUBLIC_KEY: &str = r#"
+    {
+      "kty": "OKP",
+      "crv": "Ed25519",
+      "alg": "EdDSA",
+      "x": "OasNl_txIwm8IWTT2jWNe8H3MpmeHcvl20NgTxqeGvE"
+    }
+    "#;
+
+    fn licence() -> MiningLicence {
+        MiningLicence {
+            issuer_id: "National_Mining_Authority".to_string(),
+            subject_id: "ACME_Mining_Company".to_string(),
+            subject_pk: SUBJECT_PUBLIC_KEY.to_string(),
+            issue_date: "2025-01-01T00:00:00Z".parse().unwrap(),
+            expiry_date: "2035-01-01T00:00:00Z".parse().unwrap(),
+            country_of_operation: "GB".to_string(),
+            region_of_operation: "GB-CON".to_string(),
+            sites: vec!["-5.0527,50.266".parse().unwrap()],
+            status_index: Some(94567),
+        }
+    }
+
+    #[test]
+    fn it_round_trips_licence_through_claims() {
+        let claims = CustomClaims::from(&licence());
+        assert_eq!(
+            claims
+                .claims
+                .iter()
+                .find(|claim| claim.key == "issue_date")
+                .unwrap()
+                .value,
+            "2025-01-01T00:00:00Z"
+        );
+        assert_eq!(MiningLicence::try_from(&claims).unwrap(), licence());
+
+        // Licences without sites carry no site claim
+        let without_sites = MiningLicence {
+            sites: vec![],
+            ..licence()
+        };
+        let claims = CustomClaims::from(&without_sites);
+        assert!(claims.get("site_coordinates").is_none());
+        assert_eq!(MiningLicence::try_from(&claims).unwrap(), without_sites);
+    }
+
+    #[test]
+    fn it_reports_missing_and_invalid_claims() {
+        let mut claims = CustomClaims::new();
+        claims.add("shipment_id".to_string(), "653321".to_string());
+        claims.add("issue_date".to_string(), "2025-12-01T00:00:00Z".to_string());
+        assert_eq!(
+            ProductPassport::try_from(&claims),
+            Err(CredentialError::MissingClaim("product"))
+        );
+
+        let mut licence_claims = CustomClaims::from(&licence());
+        licence_claims
+            .claims
+            .iter_mut()
+            .find(|claim| claim.key == "site_coordinates")
+            .unwrap()
+            .value = "[[200, 0]]".to_string();
+        assert!(matches!(
+            MiningLicence::try_from(&licence_claims),
+            Err(CredentialError::InvalidClaim {
+                key: "site_coordinates",
+                ..
+            })
+        ));
+
+        claims.add("product".to_string(), "Lithium".to_string());
+        claims.claims[1].value = "1st December".to_string();
+        assert!(matches!(
+            ProductPassport::try_from(&claims),
+            Err(CredentialError::InvalidDate {
+                key: "issue_date",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn it_round_trips_passport_sources_and_attributes() {
+        let passport = ProductPassport {
+            shipment_id: "653321".to_string(),
+            product: "Lithium".to_string(),
+            issue_date: "2025-12-01T00:00:00Z".parse().unwrap(),
+            sources: vec![
+                SourceShare {
+                    licence_digest: "licence-a".to_string(),
+                    mass_kg: 600,
+                },
+                SourceShare {
+                    licence_digest: "licence-b".to_string(),
+                    mass_kg: 400,
+                },
+            ],
+            declared_mass_kg: Some(1000),
+            attributes: BTreeMap::from([(
+                "recycled_content_percent".to_string(),
+                "22.5".to_string(),
+            )]),
+        };
+        let mut claims = CustomClaims::from(&passport);
+        assert_eq!(claims.get("recycled_content_percent"), Some("22.5"));
+        assert_eq!(ProductPassport::try_from(&claims).unwrap(), passport);
+
+        // Sources without a declared total are rejected
+        claims
+            .claims
+            .retain(|claim| claim.key != "declared_mass_kg");
+        assert_eq!(
+            ProductPassport::try_from(&claims),
+            Err(CredentialError::MissingClaim("declared_mass_kg"))
+        );
+        claims.add("declared_mass_kg".to_string(), "a tonne".to_string());
+        assert!(matches!(
+            ProductPassport::try_from(&claims),
+            Err(CredentialError::InvalidClaim {
+                key: "declared_mass_kg",
+                ..
+            })
+        ));
+
+        // A passport from a single licence keeps the mass it declares
+        let single = ProductPassport {
+            sources: vec![],
+            ..passport
+        };
+        let claims = CustomClaims::from(&single);
+        assert_eq!(claims.get("declared_mass_kg"), Some("1000"));
+        assert_eq!(ProductPassport::try_from(&claims).unwrap(), single);
+    }
+
+    #[test]
+    fn it_rejects_reserved_passport_attributes() {
+        let passport = |key: &str| ProductPassport {
+            shipment_id: "653321".to_string(),
+            product: "Lithium".to_string(),
+            issue_date: "2025-12-01T00:00:00Z".parse().unwrap(),
+            sources: vec![],
+            declared_mass_kg: None,
+            attributes: BTreeMap::from([(key.to_string(), "true".to_string())]),
+        };
+        assert!(passport("recycled_content_percent").validate().is_ok());
+        for key in [
+            "product",
+            "country_of_operation",
+            "passport_signing_date",
+            "nullifier",
+            "predicate:recycled_content_percent>=20",
+            "shipment_id_commitment",
+        ] {
+            assert_eq!(
+                passport(key).validate(),
+                Err(CredentialError::ReservedClaim(key.to_string()))
+            );
+        }
+        let issuer = ISSUER_SECRET_KEY.parse::<Issuer>().unwrap();
+        assert!(passport("nullifier").sign(&issuer).is_err());
+
+        // Nor can a passport signed elsewhere carry them
+        let mut claims = CustomClaims::from(&passport("recycled_content_percent"));
+        claims.add(
+            "licence_valid_when_signing_product_passport".to_string(),
+            "true".to_string(),
+        );
+        assert_eq!(
+            ProductPassport::try_from(&claims),
+            Err(CredentialError::ReservedClaim(
+                "licence_valid_when_signing_product_passport".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn it_normalises_licence_codes() {
+        let issuer = ISSUER_SECRET_KEY.parse::<Issuer>().unwrap();
+        let validator = ISSUER_PUBLIC_KEY
+            .parse::<Validator>()
+            .unwrap()
+            .with_clock("2030-01-01T00:00:00Z".parse().unwrap());
+
+        let named = MiningLicence {
+            country_of_operation: "United Kingdom".to_string(),
+            region_of_operation: "Cornwall".to_string(),
+            ..licence()
+        };
+        let token = named.sign(&issuer).unwrap();
+        assert_eq!(
+            MiningLicence::verify(&token, &validator).unwrap(),
+            licence()
+        );
+
+        let misspelt = MiningLicence {
+            region_of_operation: "Kernow".to_string(),
+            ..licence()
+        };
+        assert!(matches!(
+            misspelt.sign(&issuer),
+            Err(Err::CredentialError(CredentialError::InvalidClaim {
+                key: "region_of_operation",
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn it_signs_and_verifies_credentials() {
+        let issuer = ISSUER_SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = licence().sign(&issuer).unwrap();
+
+        let validator = ISSUER_PUBLIC_KEY
+            .parse::<Validator>()
+            .unwrap()
+            .with_clock("2030-01-01T00:00:00Z".parse().unwrap());
+        let valid_licence = MiningLicence::verify(&token, &validator).unwrap();
+        assert_eq!(valid_licence, licence());
+
+        let passport = ProductPassport {
+            shipment_id: "653321".to_string(),
+            product: "Lithium".to_string(),
+            issue_date: "2025-12-01T00:00:00Z".parse().unwrap(),
+            sources: Vec::new(),
+            declared_mass_kg: None,
+            attributes: BTreeMap::new(),
+        };
+        let issuer = SUBJECT_SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = passport.sign(&issuer).unwrap();
+
+        let validator = valid_licence.subject_validator().unwrap();
+        assert_eq!(
+            ProductPassport::verify(&token, &validator).unwrap(),
+            passport
+        );
+    }
+
+    #[test]
+    fn it_rejects_passport_as_licence() {
+        let passport = ProductPassport {
+            shipment_id: "653321".to_string(),
+            product: "Lithium".to_string(),
+            issue_date: "2025-12-01T00:00:00Z".parse().unwrap(),
+            sources: Vec::new(),
+            declared_mass_kg: None,
+            attributes: BTreeMap::new(),
+        };
+        let issuer = ISSUER_SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = passport.sign(&issuer).unwrap();
+
+        let validator = ISSUER_PUBLIC_KEY.parse::<Validator>().unwrap();
+        assert!(matches!(
+            MiningLicence::verify(&token, &validator),
+            Err(Err::CredentialError(CredentialError::MissingClaim(
+                "issuer_id"
+            )))
+        ));
+    }
+
+    #[test]
+    fn it_verifies_custody_transfer_with_previous_holder_key() {
+        let transfer = CustodyTransfer {
+            shipment_id: "653321".to_string(),
+            issuer_id: "ACME_Mining_Company".to_string(),
+            subject_id: "ACME_Smelting_Company".to_string(),
+            subject_pk: ISSUER_PUBLIC_KEY.to_string(),
+            transfer_date: "2025-12-02T00:00:00Z".parse().unwrap(),
+        };
+        let issuer = SUBJECT_SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = transfer.sign(&issuer).unwrap();
+
+        let validator = licence().subject_validator().unwrap();
+        let valid_transfer = CustodyTransfer::verify(&token, &validator).unwrap();
+        assert_eq!(valid_transfer, transfer);
+
+        let next_validator = valid_transfer.subject_validator().unwrap();
+        assert!(CustodyTransfer::verify(&token, &next_validator).is_err());
+    }
+
+    #[test]
+    fn it_signs_and_verifies_conflict_zone_list() {
+        let list = ConflictZoneList {
+            publisher_id: "Conflict_Zone_Authority".to_string(),
+            publication_date: "2025-06-01T00:00:00Z".parse().unwrap(),
+            conflict_zones: serde_json::from_str(
+                r#"{"version": 2, "zones": [{"country": "GB", "region": "GB-LND"}]}"#,
+            )
+            .unwrap(),
+        };
+        let issuer = ISSUER_SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = list.sign(&issuer).unwrap();
+
+        let validator = ISSUER_PUBLIC_KEY.parse::<Validator>().unwrap();
+        let valid_list = ConflictZoneList::verify(&token, &validator).unwrap();
+        assert_eq!(valid_list, list);
+        assert_eq!(valid_list.conflict_zones.version, 2);
+
+        let validator = SUBJECT_PUBLIC_KEY.parse::<Validator>().unwrap();
+        assert!(ConflictZoneList::verify(&token, &validator).is_err());
+    }
+
+    #[test]
+    fn it_signs_and_verifies_conflict_zone_root() {
+        let list = ConflictZoneList {
+            publisher_id: "Conflict_Zone_Authority".to_string(),
+            publication_date: "2025-06-01T00:00:00Z".parse().unwrap(),
+            conflict_zones: serde_json::from_str(
+                r#"{"version": 2, "zones": [
+                    {"country": "GB", "region": "London, City of"},
+                    {"country": "GB", "region": "GB-WAR"}
+                ]}"#,
+            )
+            .unwrap(),
+        };
+        let root = ConflictZoneRoot::from_list(&list).unwrap();
+        assert_eq!(root.version, 2);
+        assert_eq!(root.zone_count, 2);
+        assert_eq!(
+            root.root,
+            ZoneTree::build(&list.normalised().unwrap().conflict_zones)
+                .unwrap()
+                .root()
+        );
+
+        let issuer = ISSUER_SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = root.sign(&issuer).unwrap();
+        let validator = ISSUER_PUBLIC_KEY.parse::<Validator>().unwrap();
+        assert_eq!(ConflictZoneRoot::verify(&token, &validator).unwrap(), root);
+
+        // A root token is not a list, and vice versa
+        assert!(ConflictZoneList::verify(&token, &validator).is_err());
+        let list_token = list.sign(&issuer).unwrap();
+        assert!(ConflictZoneRoot::verify(&list_token, &validator).is_err());
+    }
+
+    #[test]
+    fn it_checks_licence_status() {
+        let mut list = LicenceStatusList::new(
+            "National_Mining_Authority".to_string(),
+            "2026-01-01T00:00:00Z".parse().unwrap(),
+            131_072,
+        );
+        assert_eq!(list.size(), 131_072);
+        list.revoke(0).unwrap();
+        list.revoke(94567).unwrap();
+        assert!(list.is_revoked(0).unwrap());
+        assert!(list.is_revoked(94567).unwrap());
+        assert!(!list.is_revoked(94566).unwrap());
+        assert_eq!(list.statuses[0], 0x80);
+        assert!(matches!(
+            list.is_revoked(131_072),
+            Err(CredentialError::InvalidClaim {
+                key: "status_list_index",
+                ..
+            })
+        ));
+
+        let issuer = ISSUER_SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = list.sign(&issuer).unwrap();
+        let validator = ISSUER_PUBLIC_KEY.parse::<Validator>().unwrap();
+        assert_eq!(LicenceStatusList::verify(&token, &validator).unwrap(), list);
+        assert!(MiningLicence::verify(&token, &validator).is_err());
+
+        // Licences that cannot be revoked carry no index
+        let licence = MiningLicence {
+            status_index: None,
+            ..licence()
+        };
+        let claims = CustomClaims::from(&licence);
+        assert!(claims.get("status_list_index").is_none());
+        assert_eq!(MiningLicence::try_from(&claims).unwrap(), licence);
+    }
+
+    #[test]
+    fn it_signs_and_verifies_recall_list() {
+        let list = RecallList {
+            issuer_id: "ACME_Mining_Company".to_string(),
+            publication_date: "2026-02-01T00:00:00Z".parse().unwrap(),
+            shipment_ids: vec!["653321".to_string(), "653325".to_string()],
+        };
+        assert!(list.is_recalled("653321"));
+        assert!(!list.is_recalled("65332"));
+
+        let issuer = ISSUER_SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = list.sign(&issuer).unwrap();
+        let validator = ISSUER_PUBLIC_KEY.parse::<Validator>().unwrap();
+        assert_eq!(RecallList::verify(&token, &validator).unwrap(), list);
+
+        // A passport signed with the same key is not a recall list
+        let passport = ProductPassport {
+            shipment_id: "653321".to_string(),
+            product: "Lithium".to_string(),
+            issue_date: "2025-12-01T00:00:00Z".parse().unwrap(),
+            sources: vec![],
+            declared_mass_kg: None,
+            attributes: BTreeMap::new(),
+        };
+        assert!(RecallList::verify(&passport.sign(&issuer).unwrap(), &validator).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{ClaimItem, CustomClaims};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DisclosureError {
+    #[error("Claim '{0}' is listed more than once")]
+    ListedTwice(String),
+
+    #[error("Claim '{0}' is not in the passport")]
+    UnknownClaim(String),
+
+    #[error("Claim '{0}' is not listed to be revealed, committed or hidden")]
+    Unlisted(String),
+}
+
+/// How each claim of a product passport appears in the journal, chosen by the
+/// prover so that one passport can back different proofs for, say, customs and
+/// end customers.  Every claim must be listed exactly once, so nothing is
+/// revealed or withheld by accident.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisclosureSet {
+    /// Claims committed in the clear, under their own key
+    #[serde(default)]
+    pub reveal: Vec<String>,
+    /// Claims committed as a salted hash under `<key>_commitment`, which the
+    /// prover can later open with the [`disclosure_salt`] of the proof's seed
+    #[serde(default)]
+    pub commit: Vec<String>,
+    /// Claims left out of the journal
+    #[serde(default)]
+    pub hide: Vec<String>,
+}
+
+impl DisclosureSet {
+    /// What a proof discloses when the prover does not choose: the shipment ID,
+    /// issue date and declared mass of a blended shipment, and nothing else.
+    /// A chosen set only discloses the declared mass if it lists it.
+    pub fn standard(claims: &CustomClaims) -> Self {
+        let revealed = ["shipment_id", "issue_date", "declared_mass_kg"];
+        Self {
+            reveal: revealed
+                .iter()
+                .filter(|key| claims.get(key).is_some())
+                .map(|key| key.to_string())
+                .collect(),
+            commit: Vec::new(),
+            hide: claims
+                .claims
+                .iter()
+                .map(|claim| claim.key.clone())
+                .filter(|key| !revealed.contains(&key.as_str()))
+                .collect(),
+        }
+    }
+
+    pub fn reveal(mut self, key: String) -> Self {
+        self.reveal.push(key);
+        self
+    }
+
+    pub fn commit(mut self, key: String) -> Self {
+        self.commit.push(key);
+        self
+    }
+
+    pub fn hide(mut self, key: String) -> Self {
+        self.hide.push(key);
+        self
+    }
+
+    /// Checks that the set lists every claim of the passport exactly once.
+    pub fn check(&self, claims: &CustomClaims) -> Result<(), DisclosureError> {
+        let listed: Vec<&String> = self
+            .reveal
+            .iter()
+            .chain(self.commit.iter())
+            .chain(self.hide.iter())
+            .collect();
+        for (i, key) in listed.iter().enumerate() {
+            if listed[..i].contains(key) {
+                return Err(DisclosureError::ListedTwice(key.to_string()));
+            }
+            if claims.get(key).is_none() {
+                return Err(DisclosureError::UnknownClaim(key.to_string()));
+            }
+        }
+        match claims
+            .claims
+            .iter()
+            .find(|claim| !listed.contains(&&claim.key))
+        {
+            Some(claim) => Err(DisclosureError::Unlisted(claim.key.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// The journal claims for a passport with claims `claims`, in the order
+    /// the claims are signed in, committing under salts derived from `seed`.
+    pub fn apply(
+        &self,
+        seed: &str,
+        claims: &CustomClaims,
+    ) -> Result<Vec<ClaimItem>, DisclosureError> {
+        self.check(claims)?;
+        Ok(claims
+            .claims
+            .iter()
+            .filter_map(|claim| {
+                if self.reveal.contains(&claim.key) {
+                    Some(claim.clone())
+                } else if self.commit.contains(&claim.key) {
+                    let salt = disclosure_salt(seed, &claim.key);
+                    Some(ClaimItem {
+                        key: format!("{}_commitment", claim.key),
+                        value: claim_commitment(&salt, &claim.key, &claim.value),
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Compact JSON of the set with each list sorted, as committed to the journal.
+    pub fn canonical_json(&self) -> String {
+        let sorted = |keys: &Vec<String>| {
+            let mut keys = keys.clone();
+            keys.sort();
+            keys
+        };
+        serde_json::to_string(&Self {
+            reveal: sorted(&self.reveal),
+            commit: sorted(&self.commit),
+            hide: sorted(&self.hide),
+        })
+        .expect("Could not serialise disclosure set")
+    }
+}
+
+/// URL-safe base64 salt of the commitment to claim `key` under the prover's
+/// random `seed`.  A fresh seed for each proof keeps proofs of one passport
+/// from being linked by their commitments, and the prover can open one
+/// commitment without revealing the others.
+pub fn disclosure_salt(seed: &str, key: &str) -> String {
+    let salt = Sha256::new()
+        .chain_update(b"product-passport-disclosure-salt\0")
+        .chain_update(seed)
+        .chain_update(b"\0")
+        .chain_update(key)
+        .finalize();
+    BASE64_URL_SAFE_NO_PAD.encode(salt)
+}
+
+/// URL-safe base64 commitment to `value` of claim `key` under `salt`.
+pub fn claim_commitment(salt: &str, key: &str, value: &str) -> String {
+    let commitment = Sha256::new()
+        .chain_update(b"product-passport-claim\0")
+        .chain_update(salt)
+        .chain_update(b"\0")
+        .chain_update(key)
+        .chain_update(b"\0")
+        .chain_update(value)
+        .finalize();
+    BASE64_URL_SAFE_NO_PAD.encode(commitment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims() -> CustomClaims {
+        let mut claims = CustomClaims::new();
+        claims.add("shipment_id".to_string(), "653321".to_string());
+        claims.add("product".to_string(), "Lithium".to_string());
+        claims.add("issue_date".to_string(), "2025-12-01T00:00:00Z".to_string());
+        claims
+    }
+
+    #[test]
+    fn it_applies_disclosure_sets() {
+        let set = DisclosureSet::default()
+            .reveal("product".to_string())
+            .commit("shipment_id".to_string())
+            .hide("issue_date".to_string());
+        let journal = set.apply("seed", &claims()).unwrap();
+        assert_eq!(journal.len(), 2);
+        assert_eq!(journal[0].key, "shipment_id_commitment");
+        let salt = disclosure_salt("seed", "shipment_id");
+        assert_eq!(
+            journal[0].value,
+            claim_commitment(&salt, "shipment_id", "653321")
+        );
+        assert_ne!(
+            journal[0].value,
+            claim_commitment(&salt, "shipment_id", "653322")
+        );
+        assert_eq!(journal[1].key, "product");
+        assert_eq!(journal[1].value, "Lithium");
+        // Another proof of the same passport cannot be linked to this one
+        assert_ne!(
+            set.apply("other seed", &claims()).unwrap()[0].value,
+            journal[0].value
+        );
+        assert_eq!(
+            set.canonical_json(),
+            r#"{"reveal":["product"],"commit":["shipment_id"],"hide":["issue_date"]}"#
+        );
+
+        let standard = DisclosureSet::standard(&claims());
+        assert_eq!(standard.hide, vec!["product".to_string()]);
+        let journal = standard.apply("seed", &claims()).unwrap();
+        assert_eq!(journal.len(), 2);
+        assert_eq!(journal[0].key, "shipment_id");
+        assert_eq!(journal[1].key, "issue_date");
+
+        let mut blended = claims();
+        blended.add("declared_mass_kg".to_string(), "1000".to_string());
+        let standard = DisclosureSet::standard(&blended);
+        assert_eq!(standard.reveal.last().unwrap(), "declared_mass_kg");
+        assert_eq!(standard.apply("seed", &blended).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn it_rejects_incomplete_disclosure_sets() {
+        let set = DisclosureSet::default()
+            .reveal("product".to_string())
+            .hide("issue_date".to_string());
+        assert_eq!(
+            set.check(&claims()),
+            Err(DisclosureError::Unlisted("shipment_id".to_string()))
+        );
+        assert_eq!(
+            set.clone().commit("product".to_string()).check(&claims()),
+            Err(DisclosureError::ListedTwice("product".to_string()))
+        );
+        assert_eq!(
+            set.hide("shipment_id".to_string())
+                .hide("grade".to_string())
+                .check(&claims()),
+            Err(DisclosureError::UnknownClaim("grade".to_string()))
+        );
+    }
+}
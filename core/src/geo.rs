@@ -0,0 +1,241 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Coordinates are stored in units of 10^-7 degrees (about 1 cm), so that the
+/// guest only ever compares integers.
+const SCALE: f64 = 1e7;
+
+/// A longitude/latitude position in fixed-point degrees.  Serialised as a
+/// GeoJSON position `[longitude, latitude]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Coordinate {
+    pub lon: i64,
+    pub lat: i64,
+}
+
+impl Coordinate {
+    /// Rounds degrees to the nearest fixed-point unit, rejecting positions off
+    /// the globe.
+    pub fn from_degrees(lon: f64, lat: f64) -> Result<Self, String> {
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err(format!("longitude {lon} is out of range"));
+        }
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(format!("latitude {lat} is out of range"));
+        }
+        Ok(Self {
+            lon: (lon * SCALE).round() as i64,
+            lat: (lat * SCALE).round() as i64,
+        })
+    }
+
+    pub fn to_degrees(self) -> (f64, f64) {
+        (self.lon as f64 / SCALE, self.lat as f64 / SCALE)
+    }
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (lon, lat) = self.to_degrees();
+        write!(f, "{lon},{lat}")
+    }
+}
+
+/// Parses `LON,LAT` in degrees, e.g. `-5.05,50.26`.
+impl FromStr for Coordinate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lon, lat) = s
+            .split_once(',')
+            .ok_or_else(|| format!("expected LON,LAT, got '{s}'"))?;
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|err| format!("invalid degrees '{value}': {err}"))
+        };
+        Self::from_degrees(parse(lon)?, parse(lat)?)
+    }
+}
+
+impl Serialize for Coordinate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (lon, lat) = self.to_degrees();
+        [lon, lat].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Coordinate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // GeoJSON positions may carry an altitude, which is ignored
+        let position = Vec::<f64>::deserialize(deserializer)?;
+        match position.as_slice() {
+            [lon, lat] | [lon, lat, _] => Self::from_degrees(*lon, *lat).map_err(D::Error::custom),
+            _ => Err(D::Error::custom(
+                "expected a position of [longitude, latitude]",
+            )),
+        }
+    }
+}
+
+/// A closed ring of positions; GeoJSON repeats the first position at the end.
+pub type Ring = Vec<Coordinate>;
+
+/// An outer ring followed by any holes.
+pub type Polygon = Vec<Ring>;
+
+/// A GeoJSON `Polygon` or `MultiPolygon` geometry.  Rings are treated as
+/// planar in longitude/latitude, so areas crossing the antimeridian must be
+/// split.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(tag = "type", content = "coordinates")]
+pub enum Geometry {
+    Polygon(Polygon),
+    MultiPolygon(Vec<Polygon>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+impl Geometry {
+    /// Whether `point` lies inside the geometry or on its boundary.  Points on
+    /// the boundary of a hole also count as inside, so that rounding never
+    /// moves a site out of a zone.
+    pub fn contains(&self, point: &Coordinate) -> bool {
+        match self {
+            Geometry::Polygon(polygon) => polygon_contains(polygon, point),
+            Geometry::MultiPolygon(polygons) => polygons
+                .iter()
+                .any(|polygon| polygon_contains(polygon, point)),
+        }
+    }
+}
+
+fn polygon_contains(polygon: &Polygon, point: &Coordinate) -> bool {
+    let Some((outer, holes)) = polygon.split_first() else {
+        return false;
+    };
+    ring_position(outer, point) != Position::Outside
+        && holes
+            .iter()
+            .all(|hole| ring_position(hole, point) != Position::Inside)
+}
+
+/// Ray casting towards increasing longitude, in exact integer arithmetic.
+fn ring_position(ring: &Ring, point: &Coordinate) -> Position {
+    let mut inside = false;
+    // Pair each vertex with the next, closing the ring if it is left open
+    let next = ring.iter().skip(1).chain(ring.first());
+    for (a, b) in ring.iter().zip(next) {
+        let (dx, dy) = (i128::from(b.lon - a.lon), i128::from(b.lat - a.lat));
+        let (px, py) = (i128::from(point.lon - a.lon), i128::from(point.lat - a.lat));
+
+        let on_line = dx * py == dy * px;
+        let within_box = a.lon.min(b.lon) <= point.lon
+            && point.lon <= a.lon.max(b.lon)
+            && a.lat.min(b.lat) <= point.lat
+            && point.lat <= a.lat.max(b.lat);
+        if on_line && within_box {
+            return Position::Boundary;
+        }
+
+        // The edge straddles the point's latitude and crosses the ray east of it
+        if (a.lat > point.lat) != (b.lat > point.lat) {
+            let (lhs, rhs) = (px * dy, py * dx);
+            if (dy > 0 && lhs < rhs) || (dy < 0 && lhs > rhs) {
+                inside = !inside;
+            }
+        }
+    }
+    if inside {
+        Position::Inside
+    } else {
+        Position::Outside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(json: &str) -> Geometry {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn point(s: &str) -> Coordinate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn it_contains_points_in_polygons() {
+        // A square with a square hole in the middle
+        let square = geometry(
+            r#"{"type": "Polygon", "coordinates": [
+                [[0, 0], [4, 0], [4, 4], [0, 4], [0, 0]],
+                [[1, 1], [3, 1], [3, 3], [1, 3], [1, 1]]
+            ]}"#,
+        );
+        assert!(square.contains(&point("0.5,0.5")));
+        assert!(!square.contains(&point("2,2")));
+        assert!(!square.contains(&point("5,2")));
+        assert!(!square.contains(&point("-0.0000001,2")));
+        // Boundaries of the outer ring and of holes count as inside
+        assert!(square.contains(&point("0,2")));
+        assert!(square.contains(&point("4,4")));
+        assert!(square.contains(&point("1,2")));
+        assert!(square.contains(&point("2,3")));
+    }
+
+    #[test]
+    fn it_contains_points_in_multipolygons() {
+        // A triangle and a concave polygon, with an open ring
+        let zones = geometry(
+            r#"{"type": "MultiPolygon", "coordinates": [
+                [[[-5.5, 50.0], [-5.0, 50.0], [-5.25, 50.5], [-5.5, 50.0]]],
+                [[[10, 10], [14, 10], [14, 14], [12, 11], [10, 14]]]
+            ]}"#,
+        );
+        assert!(zones.contains(&point("-5.25,50.25")));
+        assert!(!zones.contains(&point("-5.45,50.4")));
+        assert!(zones.contains(&point("11,11.5")));
+        assert!(!zones.contains(&point("12,13")));
+        assert!(zones.contains(&point("10,12")));
+    }
+
+    #[test]
+    fn it_parses_coordinates() {
+        let site = point("-5.0527, 50.2660");
+        assert_eq!(
+            site,
+            Coordinate {
+                lon: -50_527_000,
+                lat: 502_660_000
+            }
+        );
+        assert_eq!(site.to_string(), "-5.0527,50.266");
+        let json = serde_json::to_string(&site).unwrap();
+        assert_eq!(json, "[-5.0527,50.266]");
+        assert_eq!(serde_json::from_str::<Coordinate>(&json).unwrap(), site);
+        assert_eq!(
+            serde_json::from_str::<Coordinate>("[-5.0527, 50.266, 120.0]").unwrap(),
+            site
+        );
+
+        assert!("-5.0527".parse::<Coordinate>().is_err());
+        assert!("181,0".parse::<Coordinate>().is_err());
+        assert!("0,-91".parse::<Coordinate>().is_err());
+        assert!(serde_json::from_str::<Coordinate>("[1]").is_err());
+        assert!(
+            serde_json::from_str::<Geometry>(r#"{"type": "Point", "coordinates": [0, 0]}"#)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use thiserror::Error;
+
+/// `alpha-2, alpha-3, name` for each ISO 3166-1 country, sorted by alpha-2.
+const COUNTRIES: &str = include_str!("../data/iso3166-1.tsv");
+
+/// `code, name, parent` for each ISO 3166-2 subdivision, sorted by code.  The
+/// parent is empty for top-level subdivisions.
+const SUBDIVISIONS: &str = include_str!("../data/iso3166-2.tsv");
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CodeError {
+    #[error("Unknown ISO 3166-1 country '{0}'")]
+    UnknownCountry(String),
+
+    #[error("Unknown ISO 3166-2 subdivision '{region}' of {country}")]
+    UnknownRegion { country: String, region: String },
+
+    #[error("Subdivision name '{region}' is ambiguous in {country}, use its ISO 3166-2 code")]
+    AmbiguousRegion { country: String, region: String },
+
+    #[error("Excluded region {region} is not within {zone}")]
+    ExclusionOutsideZone { region: String, zone: String },
+}
+
+// Codes are found by bisecting the sorted tables in place, without parsing
+// them, as the guest looks up regions for every conflict zone it checks.
+// Names are not sorted, so lookups by name scan the table.
+
+/// The line of `table` whose first field is `key`, by binary search over the
+/// table's text.  The table must be sorted by its first field, with any
+/// comment lines before the rows.
+fn find_row(table: &'static str, key: &str) -> Option<&'static str> {
+    let bytes = table.as_bytes();
+    let mut low: usize = table
+        .lines()
+        .take_while(|line| line.starts_with('#'))
+        .map(|line| line.len() + 1)
+        .sum();
+    let mut high = table.len();
+    while low < high {
+        // The line around the midpoint, which starts no earlier than `low`
+        let mid = low + (high - low) / 2;
+        let start = bytes[low..mid]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(low, |i| low + i + 1);
+        let end = bytes[start..high]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(high, |i| start + i);
+        let line = &table[start..end];
+        let field = line.split_once('\t').map_or(line, |(field, _)| field);
+        match field.cmp(key) {
+            std::cmp::Ordering::Equal => return Some(line),
+            std::cmp::Ordering::Less => low = end + 1,
+            std::cmp::Ordering::Greater => high = start,
+        }
+    }
+    None
+}
+
+fn rows(table: &'static str) -> impl Iterator<Item = (&'static str, &'static str)> {
+    table
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('\t'))
+}
+
+/// `(alpha-2, alpha-3, name)` for each country.
+fn countries() -> impl Iterator<Item = (&'static str, &'static str, &'static str)> {
+    rows(COUNTRIES).filter_map(|(alpha_2, rest)| {
+        let (alpha_3, name) = rest.split_once('\t')?;
+        Some((alpha_2, alpha_3, name))
+    })
+}
+
+/// `(code, name, parent)` for each subdivision.
+pub(crate) fn subdivisions() -> impl Iterator<Item = (&'static str, &'static str, &'static str)> {
+    rows(SUBDIVISIONS).filter_map(|(code, rest)| {
+        let (name, parent) = rest.split_once('\t')?;
+        Some((code, name, parent))
+    })
+}
+
+/// Compares names case-insensitively, including non-ASCII letters.
+fn same_name(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Normalises a country given as an ISO 3166-1 alpha-2 or alpha-3 code or its
+/// English name, ignoring case, to its alpha-2 code, e.g. `gbr` to `GB`.
+pub fn normalise_country(country: &str) -> Result<&'static str, CodeError> {
+    let country = country.trim();
+    if country.len() == 2 {
+        if let Some(row) = find_row(COUNTRIES, &country.to_ascii_uppercase()) {
+            return Ok(&row[..2]);
+        }
+    }
+    countries()
+        .find(|(_, alpha_3, _)| alpha_3.eq_ignore_ascii_case(country))
+        .or_else(|| countries().find(|(_, _, name)| same_name(name, country)))
+        .map(|(alpha_2, _, _)| alpha_2)
+        .ok_or_else(|| CodeError::UnknownCountry(country.to_string()))
+}
+
+/// Normalises a subdivision of the alpha-2 `country`, given as its ISO 3166-2
+/// code with or without the country prefix or as its name, ignoring case, to
+/// its full code, e.g. `Cornwall` or `con` in `GB` to `GB-CON`.
+pub fn normalise_region(country: &str, region: &str) -> Result<&'static str, CodeError> {
+    let region = region.trim();
+    let in_country = || {
+        subdivisions().filter(|(code, _, _)| {
+            code.split_once('-')
+                .is_some_and(|(code_country, _)| code_country.eq_ignore_ascii_case(country))
+        })
+    };
+
+    // Either the full code or the part after the country prefix
+    let local_code = match region.split_once('-') {
+        Some((region_country, local)) if region_country.eq_ignore_ascii_case(country) => local,
+        _ => region,
+    };
+    let code = format!("{country}-{local_code}").to_ascii_uppercase();
+    if let Some(row) = find_row(SUBDIVISIONS, &code) {
+        return Ok(&row[..code.len()]);
+    }
+
+    let mut matches = in_country().filter(|(_, name, _)| same_name(name, region));
+    match (matches.next(), matches.next()) {
+        (Some((code, _, _)), None) => Ok(code),
+        (Some(_), Some(_)) => Err(CodeError::AmbiguousRegion {
+            country: country.to_string(),
+            region: region.to_string(),
+        }),
+        (None, _) => Err(CodeError::UnknownRegion {
+            country: country.to_string(),
+            region: region.to_string(),
+        }),
+    }
+}
+
+/// The subdivision an ISO 3166-2 code lies within, e.g. `GB-ENG` for `GB-CON`.
+pub fn parent_region(code: &str) -> Option<&'static str> {
+    find_row(SUBDIVISIONS, code)
+        .and_then(|row| row.rsplit_once('\t'))
+        .map(|(_, parent)| parent)
+        .filter(|parent| !parent.is_empty())
+}
+
+/// Whether the ISO 3166-2 `region` is `ancestor` or lies anywhere within it.
+pub fn region_within(region: &str, ancestor: &str) -> bool {
+    let mut region = region;
+    loop {
+        if region == ancestor {
+            return true;
+        }
+        match parent_region(region) {
+            Some(parent) => region = parent,
+            None => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_normalises_countries() {
+        assert_eq!(normalise_country("GB"), Ok("GB"));
+        assert_eq!(normalise_country(" gb "), Ok("GB"));
+        assert_eq!(normalise_country("GBR"), Ok("GB"));
+        assert_eq!(normalise_country("united kingdom"), Ok("GB"));
+        assert_eq!(normalise_country("Côte d'Ivoire"), Ok("CI"));
+        assert_eq!(
+            normalise_country("UK"),
+            Err(CodeError::UnknownCountry("UK".to_string()))
+        );
+        assert!(normalise_country("").is_err());
+    }
+
+    #[test]
+    fn it_normalises_regions() {
+        assert_eq!(normalise_region("GB", "GB-CON"), Ok("GB-CON"));
+        assert_eq!(normalise_region("GB", "gb-con"), Ok("GB-CON"));
+        assert_eq!(normalise_region("GB", "CON"), Ok("GB-CON"));
+        assert_eq!(normalise_region("GB", "Cornwall"), Ok("GB-CON"));
+        assert_eq!(normalise_region("GB", "CORNWALL"), Ok("GB-CON"));
+        assert_eq!(normalise_region("FR", "Paris"), Ok("FR-75"));
+
+        // Spelling variants and codes of other countries are rejected
+        assert_eq!(
+            normalise_region("GB", "Kernow"),
+            Err(CodeError::UnknownRegion {
+                country: "GB".to_string(),
+                region: "Kernow".to_string()
+            })
+        );
+        assert!(normalise_region("FR", "GB-CON").is_err());
+        assert!(normalise_region("FR", "Cornwall").is_err());
+        assert!(matches!(
+            normalise_region("BD", "Dhaka"),
+            Err(CodeError::AmbiguousRegion { .. })
+        ));
+    }
+
+    #[test]
+    fn it_nests_regions() {
+        assert_eq!(parent_region("GB-CON"), Some("GB-ENG"));
+        assert_eq!(parent_region("GB-ENG"), None);
+        assert_eq!(parent_region("XX-YYY"), None);
+        assert!(region_within("GB-CON", "GB-CON"));
+        assert!(region_within("GB-CON", "GB-ENG"));
+        assert!(region_within("FR-75", "FR-IDF"));
+        assert!(!region_within("GB-ENG", "GB-CON"));
+        assert!(!region_within("GB-CON", "GB-SCT"));
+    }
+
+    #[test]
+    fn it_bisects_sorted_tables() {
+        for table in [COUNTRIES, SUBDIVISIONS] {
+            let keys: Vec<&str> = rows(table).map(|(key, _)| key).collect();
+            assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+            for key in keys {
+                assert!(find_row(table, key).unwrap().starts_with(key));
+            }
+        }
+        assert_eq!(find_row(COUNTRIES, "AD"), Some("AD\tAND\tAndorra"));
+        assert_eq!(find_row(COUNTRIES, "AA"), None);
+        assert_eq!(find_row(COUNTRIES, "ZZ"), None);
+        assert_eq!(
+            find_row(SUBDIVISIONS, "GB-CON"),
+            Some("GB-CON\tCornwall\tGB-ENG")
+        );
+        assert_eq!(find_row(SUBDIVISIONS, "GB"), None);
+    }
+
+    #[test]
+    fn it_embeds_complete_tables() {
+        assert_eq!(countries().count(), 249);
+        assert!(countries().all(|(alpha_2, alpha_3, _)| alpha_2.len() == 2 && alpha_3.len() == 3));
+        assert!(subdivisions().count() > 5000);
+        assert!(subdivisions().all(|(code, _, parent)| {
+            let country = &code[..2];
+            normalise_country(country) == Ok(country)
+                && (parent.is_empty()
+                    || parent.starts_with(country)
+                        && subdivisions().any(|(code, _, _)| code == parent))
+        }));
+    }
+}
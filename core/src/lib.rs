@@ -16,30 +16,350 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use base64::prelude::*;
+use chrono::{DateTime, Duration, Utc};
 use jwt_compact::{
-    alg::{Rsa, RsaPrivateKey, RsaPublicKey},
+    alg::{Ed25519, Es256, Rsa, RsaPrivateKey, RsaPublicKey},
     jwk::{JsonWebKey, JwkError},
-    AlgorithmExt, Claims, CreationError, Header, ParseError, Token, UntrustedToken,
+    AlgorithmExt, Claims, CreationError, Header, ParseError, TimeOptions, Token, UntrustedToken,
     ValidationError,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::ops::Deref;
 use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(Serialize, Deserialize, Debug)]
+mod credentials;
+mod disclosure;
+mod geo;
+mod iso3166;
+mod merkle;
+mod predicate;
+
+pub use credentials::{
+    format_date, ConflictZoneList, ConflictZoneRoot, CredentialError, CustodyTransfer,
+    LicenceStatusList, MiningLicence, ProductPassport, RecallList, SourceShare, JOURNAL_CLAIMS,
+    PASSPORT_CLAIMS,
+};
+pub use disclosure::{claim_commitment, disclosure_salt, DisclosureError, DisclosureSet};
+pub use geo::{Coordinate, Geometry, Polygon, Ring};
+pub use iso3166::{normalise_country, normalise_region, parent_region, region_within, CodeError};
+pub use merkle::{zone_keys, KeyWitness, LeafProof, MerkleError, ZoneTree, ZoneWitness};
+pub use predicate::{Comparator, Predicate, PredicateError};
+
+/// An entry of a conflict zone list.  It covers a whole country if `region` is
+/// absent or `*`, otherwise the region and every subdivision within it, less
+/// any regions in `except`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ConflictZone {
     pub country: String,
-    pub region: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Regions exempt from the zone, with every subdivision within them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub except: Vec<String>,
+    /// Extent of the zone; licensed sites inside it are rejected however the
+    /// region is spelt.  Omitted from the JSON, and so from the digest, if absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub area: Option<Geometry>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ConflictZones {
+    #[serde(default)]
+    pub version: u32,
     pub zones: Vec<ConflictZone>,
 }
 
+/// The kind of conflict zone entry that a mining licence matched.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneMatch {
+    /// A zone covering the whole country of operation
+    Country,
+    /// A zone covering the region of operation, or a region containing it
+    Region,
+    /// A zone whose area contains a licensed site
+    Area,
+}
+
+impl std::fmt::Display for ZoneMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZoneMatch::Country => write!(f, "country"),
+            ZoneMatch::Region => write!(f, "region"),
+            ZoneMatch::Area => write!(f, "area"),
+        }
+    }
+}
+
+impl FromStr for ZoneMatch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "country" => Ok(ZoneMatch::Country),
+            "region" => Ok(ZoneMatch::Region),
+            "area" => Ok(ZoneMatch::Area),
+            _ => Err(format!("Unknown conflict zone match '{s}'")),
+        }
+    }
+}
+
+impl ConflictZone {
+    /// Returns the zone with its country as an ISO 3166-1 alpha-2 code and its
+    /// regions as ISO 3166-2 codes, e.g. `GB`/`Cornwall` as `GB`/`GB-CON`.  A
+    /// `*` region becomes absent, and excluded regions must lie within the zone.
+    pub fn normalised(&self) -> Result<Self, CodeError> {
+        let country = normalise_country(&self.country)?;
+        let region = match self.region.as_deref().map(str::trim) {
+            None | Some("*") => None,
+            Some(region) => Some(normalise_region(country, region)?),
+        };
+        let except = self
+            .except
+            .iter()
+            .map(|excluded| {
+                let excluded = normalise_region(country, excluded)?;
+                match region {
+                    Some(region) if !region_within(excluded, region) => {
+                        Err(CodeError::ExclusionOutsideZone {
+                            region: excluded.to_string(),
+                            zone: region.to_string(),
+                        })
+                    }
+                    _ => Ok(excluded.to_string()),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            country: country.to_string(),
+            region: region.map(str::to_string),
+            except,
+            area: self.area.clone(),
+        })
+    }
+
+    /// Whether the zone covers a licence for the normalised `country` and
+    /// `region`, and if so which kind of entry matched.  Areas are matched
+    /// against sites separately, see [`Geometry::contains`].
+    pub fn matches(&self, country: &str, region: &str) -> Option<ZoneMatch> {
+        if self.country != country
+            || self
+                .except
+                .iter()
+                .any(|excluded| region_within(region, excluded))
+        {
+            return None;
+        }
+        match &self.region {
+            None => Some(ZoneMatch::Country),
+            Some(zone_region) if region_within(region, zone_region) => Some(ZoneMatch::Region),
+            Some(_) => None,
+        }
+    }
+}
+
+impl ConflictZones {
+    /// Returns the list with every zone normalised to ISO 3166 codes.
+    pub fn normalised(&self) -> Result<Self, CodeError> {
+        Ok(Self {
+            version: self.version,
+            zones: self
+                .zones
+                .iter()
+                .map(ConflictZone::normalised)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// The first zone covering a licence for the normalised `country` and
+    /// `region`, with the kind of entry that matched.
+    pub fn find_match(&self, country: &str, region: &str) -> Option<(&ConflictZone, ZoneMatch)> {
+        self.zones
+            .iter()
+            .find_map(|zone| zone.matches(country, region).map(|kind| (zone, kind)))
+    }
+
+    /// The first zone whose area contains `site`.
+    pub fn find_area_match(&self, site: &Coordinate) -> Option<&ConflictZone> {
+        self.zones
+            .iter()
+            .find(|zone| zone.area.as_ref().is_some_and(|area| area.contains(site)))
+    }
+
+    /// Compact JSON of the version and the sorted, de-duplicated zones, so that
+    /// formatting and ordering of the source file do not affect the digest.
+    pub fn canonical_json(&self) -> String {
+        let mut zones = self.zones.clone();
+        for zone in zones.iter_mut() {
+            zone.except.sort();
+            zone.except.dedup();
+        }
+        zones.sort();
+        zones.dedup();
+        let canonical = ConflictZones {
+            version: self.version,
+            zones,
+        };
+        serde_json::to_string(&canonical).expect("Could not serialise conflict zones")
+    }
+
+    /// URL-safe base64 SHA-256 digest of the canonical JSON.
+    pub fn digest(&self) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(self.canonical_json()))
+    }
+}
+
+/// RFC 7638 thumbprint of a JWK: the SHA-256 digest of its required members
+/// (`e`, `kty`, `n` for RSA; `crv`, `kty`, `x`, `y` for EC; `crv`, `kty`, `x`
+/// for OKP), base64url-encoded without padding.  Private keys have the same
+/// thumbprint as their public keys.
+pub fn jwk_thumbprint(jwk: &str) -> Result<String, Err> {
+    let jwk = serde_json::from_str::<JsonWebKey>(jwk)?;
+    Ok(BASE64_URL_SAFE_NO_PAD.encode(jwk.thumbprint::<Sha256>()))
+}
+
+/// URL-safe base64 SHA-256 digest of a token, by which passports refer to the
+/// licences they were sourced under.  Surrounding whitespace is ignored.
+pub fn token_digest(token: &str) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(token.trim()))
+}
+
+/// Order of the P-256 group, big-endian.
+const P256_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63, 0x25, 0x51,
+];
+
+/// Half the order of the P-256 group, rounded down, big-endian.
+const P256_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xde, 0x73, 0x7d, 0x56, 0xd3, 0x8b, 0xcf, 0x42, 0x79, 0xdc, 0xe5, 0x61, 0x7e, 0x31, 0x92, 0xa8,
+];
+
+/// Replaces `s` in a raw ES256 signature `r || s` with `n - s`, which
+/// verifies just the same.
+fn negate_es256_s(signature: &mut [u8]) {
+    let mut borrow = 0;
+    for i in (0..32).rev() {
+        let difference = P256_ORDER[i] as i16 - signature[32 + i] as i16 - borrow;
+        borrow = (difference < 0) as i16;
+        signature[32 + i] = difference.rem_euclid(256) as u8;
+    }
+}
+
+/// The signature of a compact JWS, with an ES256 `s` reduced to the lower of
+/// its two valid values.  RS256, PS256 and EdDSA signatures have no second
+/// form for the same key and content.
+fn normalised_signature(token: &str) -> Result<Vec<u8>, Err> {
+    let token = UntrustedToken::new(token.trim())?;
+    let mut signature = token.signature_bytes().to_vec();
+    if token.algorithm() == "ES256"
+        && signature.len() == 64
+        && signature[32..] > P256_HALF_ORDER[..]
+    {
+        negate_es256_s(&mut signature);
+    }
+    Ok(signature)
+}
+
+/// URL-safe base64 nullifier of a passport token in a verifier's `context`.
+/// It is the same every time the passport is proved in that context, so the
+/// verifier can spot reuse, but unlinkable across contexts.  It hashes the
+/// passport's normalised signature, which the guest never commits, rather
+/// than its claims, which are guessable or revealed, so only the holder and
+/// the issuer of the token can recompute it.
+pub fn nullifier(passport: &str, context: &str) -> Result<String, Err> {
+    let mut hasher = Sha256::new();
+    hasher.update(b"product-passport-nullifier\0");
+    hasher.update(normalised_signature(passport)?);
+    hasher.update(b"\0");
+    hasher.update(context);
+    Ok(BASE64_URL_SAFE_NO_PAD.encode(hasher.finalize()))
+}
+
+/// Options chosen by the prover and read by the guest after the tokens.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProofOptions {
+    /// Also commit the full JWKs of the authorities and the final holder,
+    /// not just their thumbprints
+    pub commit_full_keys: bool,
+    /// Abort instead of proving when the passport was issued outside the
+    /// licence's validity period; committed to the journal as `strict_mode`
+    pub strict: bool,
+    /// Signed [`LicenceStatusList`] of the national mining authority; when
+    /// given, every licence must carry a status index that is not revoked
+    pub licence_status_list: Option<String>,
+    /// Signed [`RecallList`] of the mining company; when given, the passport
+    /// must not be on it and must not postdate it
+    pub recall_list: Option<String>,
+    /// Verifier-chosen context, e.g. a customer ID, in which to commit the
+    /// passport's [`nullifier`]
+    pub nullifier_context: Option<String>,
+    /// Which passport claims to reveal, commit to or hide; by default the
+    /// shipment ID, issue date and declared mass are revealed and the rest
+    /// hidden
+    pub disclosure: Option<DisclosureSet>,
+    /// Statements about numeric passport claims, of which only the statement
+    /// and whether it holds are committed
+    pub predicates: Vec<Predicate>,
+}
+
+impl ProofOptions {
+    pub fn commit_full_keys(mut self, commit_full_keys: bool) -> Self {
+        self.commit_full_keys = commit_full_keys;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn licence_status_list(mut self, licence_status_list: Option<String>) -> Self {
+        self.licence_status_list = licence_status_list;
+        self
+    }
+
+    pub fn recall_list(mut self, recall_list: Option<String>) -> Self {
+        self.recall_list = recall_list;
+        self
+    }
+
+    pub fn nullifier_context(mut self, nullifier_context: Option<String>) -> Self {
+        self.nullifier_context = nullifier_context;
+        self
+    }
+
+    pub fn disclosure(mut self, disclosure: Option<DisclosureSet>) -> Self {
+        self.disclosure = disclosure;
+        self
+    }
+
+    /// Predicates stating the same thing, such as `x>=20` and `x>=20.0`, are
+    /// kept once, as the guest commits each statement under one key.
+    pub fn predicates(mut self, predicates: Vec<Predicate>) -> Self {
+        self.predicates = Vec::new();
+        for predicate in predicates {
+            let key = predicate.journal_key();
+            if !self.predicates.iter().any(|kept| kept.journal_key() == key) {
+                self.predicates.push(predicate);
+            }
+        }
+        self
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PublicOutput {
-    pub pks: Vec<String>,
+    /// RFC 7638 thumbprints of the national mining authority key and the
+    /// conflict zone authority key, in that order
+    pub fingerprints: Vec<String>,
+    /// The full JWKs behind `fingerprints`, only committed when
+    /// [`ProofOptions::commit_full_keys`] is set
+    pub pks: Option<Vec<String>>,
     pub claims: CustomClaims,
 }
 
@@ -59,6 +379,12 @@ pub enum Err {
 
     #[error("Failed to parse key string: {0}")]
     KeyParseError(#[from] serde_json::Error),
+
+    #[error("Unsupported key algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+
+    #[error("Invalid credential: {0}")]
+    CredentialError(#[from] CredentialError),
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -67,7 +393,7 @@ pub struct ClaimItem {
     pub value: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct CustomClaims {
     pub claims: Vec<ClaimItem>,
 }
@@ -88,6 +414,14 @@ impl CustomClaims {
         self.claims.push(ClaimItem { key, value });
     }
 
+    /// Returns the value of the first claim with the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.claims
+            .iter()
+            .find(|claim| claim.key == key)
+            .map(|claim| claim.value.as_str())
+    }
+
     pub fn pretty_print(&self) -> String {
         let mut result = "{".to_string();
         let mut custom_claims = self.claims.iter();
@@ -121,57 +455,291 @@ impl std::fmt::Display for CustomClaims {
     }
 }
 
+/// Signature algorithms supported for licences and passports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningAlgorithm {
+    Rs256,
+    Ps256,
+    Es256,
+    EdDsa,
+}
+
+impl SigningAlgorithm {
+    /// Returns the JWS `alg` name of the algorithm.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SigningAlgorithm::Rs256 => "RS256",
+            SigningAlgorithm::Ps256 => "PS256",
+            SigningAlgorithm::Es256 => "ES256",
+            SigningAlgorithm::EdDsa => "EdDSA",
+        }
+    }
+
+    /// Selects the algorithm from the `kty`/`crv` of a JWK and its optional `alg` field.
+    /// RSA keys without an `alg` field default to RS256.
+    fn from_jwk(jwk: &JsonWebKey, alg: Option<&str>) -> Result<Self, Err> {
+        let selected = match (jwk, alg) {
+            (JsonWebKey::Rsa { .. }, None | Some("RS256")) => SigningAlgorithm::Rs256,
+            (JsonWebKey::Rsa { .. }, Some("PS256")) => SigningAlgorithm::Ps256,
+            (JsonWebKey::EllipticCurve { curve, .. }, None | Some("ES256")) if curve == "P-256" => {
+                SigningAlgorithm::Es256
+            }
+            (JsonWebKey::KeyPair { curve, .. }, None | Some("EdDSA")) if curve == "Ed25519" => {
+                SigningAlgorithm::EdDsa
+            }
+            _ => {
+                return Err(Err::UnsupportedAlgorithm(format!(
+                    "kty {} with alg {}",
+                    jwk.key_type(),
+                    alg.unwrap_or("<none>")
+                )))
+            }
+        };
+        Ok(selected)
+    }
+
+    fn rsa(&self) -> Rsa {
+        match self {
+            SigningAlgorithm::Ps256 => Rsa::ps256(),
+            _ => Rsa::rs256(),
+        }
+    }
+}
+
+impl FromStr for SigningAlgorithm {
+    type Err = Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            SigningAlgorithm::Rs256,
+            SigningAlgorithm::Ps256,
+            SigningAlgorithm::Es256,
+            SigningAlgorithm::EdDsa,
+        ]
+        .into_iter()
+        .find(|alg| alg.name().eq_ignore_ascii_case(s))
+        .ok_or_else(|| Err::UnsupportedAlgorithm(s.to_string()))
+    }
+}
+
+/// Only the `alg` member is needed on top of what `JsonWebKey` parses.
+#[derive(Deserialize)]
+struct JwkAlg {
+    alg: Option<String>,
+}
+
+fn parse_jwk(jwk_str: &str) -> Result<(JsonWebKey<'_>, SigningAlgorithm), Err> {
+    let jwk = serde_json::from_str::<JsonWebKey>(jwk_str)?;
+    let jwk_alg = serde_json::from_str::<JwkAlg>(jwk_str)?;
+    let alg = SigningAlgorithm::from_jwk(&jwk, jwk_alg.alg.as_deref())?;
+    Ok((jwk, alg))
+}
+
+pub enum SigningKey {
+    Rsa(Box<RsaPrivateKey>),
+    Es256(<Es256 as jwt_compact::Algorithm>::SigningKey),
+    EdDsa(<Ed25519 as jwt_compact::Algorithm>::SigningKey),
+}
+
+pub enum VerifyingKey {
+    Rsa(RsaPublicKey),
+    Es256(<Es256 as jwt_compact::Algorithm>::VerifyingKey),
+    EdDsa(<Ed25519 as jwt_compact::Algorithm>::VerifyingKey),
+}
+
+impl VerifyingKey {
+    fn from_jwk(jwk: &JsonWebKey, alg: SigningAlgorithm) -> Result<Self, Err> {
+        let p_k = match alg {
+            SigningAlgorithm::Rs256 | SigningAlgorithm::Ps256 => {
+                VerifyingKey::Rsa(RsaPublicKey::try_from(jwk)?)
+            }
+            SigningAlgorithm::Es256 => VerifyingKey::Es256(jwk.try_into()?),
+            SigningAlgorithm::EdDsa => VerifyingKey::EdDsa(jwk.try_into()?),
+        };
+        Ok(p_k)
+    }
+}
+
+/// Registered claims (RFC 7519 section 4.1) to set on a generated token.
+#[derive(Debug, Default, Clone)]
+pub struct RegisteredClaims {
+    pub expiration: Option<DateTime<Utc>>,
+    pub not_before: Option<DateTime<Utc>>,
+    pub issued_at: Option<DateTime<Utc>>,
+    pub issuer: Option<String>,
+    pub subject: Option<String>,
+    pub jwt_id: Option<String>,
+}
+
+impl RegisteredClaims {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn expires_at(mut self, expiration: DateTime<Utc>) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    pub fn not_before(mut self, not_before: DateTime<Utc>) -> Self {
+        self.not_before = Some(not_before);
+        self
+    }
+
+    pub fn issued_at(mut self, issued_at: DateTime<Utc>) -> Self {
+        self.issued_at = Some(issued_at);
+        self
+    }
+
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    pub fn jwt_id(mut self, jwt_id: impl Into<String>) -> Self {
+        self.jwt_id = Some(jwt_id.into());
+        self
+    }
+
+    fn to_claims(&self, custom: &CustomClaims) -> Claims<TokenClaims> {
+        let mut claims = Claims::new(TokenClaims {
+            iss: self.issuer.clone(),
+            sub: self.subject.clone(),
+            jti: self.jwt_id.clone(),
+            custom: custom.clone(),
+        });
+        claims.expiration = self.expiration;
+        claims.not_before = self.not_before;
+        claims.issued_at = self.issued_at;
+        claims
+    }
+}
+
+/// Token payload: the custom claims alongside the registered claims that
+/// `jwt_compact::Claims` does not carry itself (`exp`, `nbf` and `iat` do).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TokenClaims {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    #[serde(flatten)]
+    pub custom: CustomClaims,
+}
+
+impl Deref for TokenClaims {
+    type Target = CustomClaims;
+
+    fn deref(&self) -> &Self::Target {
+        &self.custom
+    }
+}
+
 pub struct Issuer {
-    alg: Rsa,
-    s_k: RsaPrivateKey,
-    pub p_k: RsaPublicKey,
+    alg: SigningAlgorithm,
+    s_k: SigningKey,
+    pub p_k: VerifyingKey,
 }
 
 impl Issuer {
+    pub fn algorithm(&self) -> SigningAlgorithm {
+        self.alg
+    }
+
     pub fn generate_token(&self, claims: &CustomClaims) -> Result<String, Err> {
+        self.generate_token_with_registered_claims(claims, &RegisteredClaims::new())
+    }
+
+    pub fn generate_token_with_registered_claims(
+        &self,
+        claims: &CustomClaims,
+        registered: &RegisteredClaims,
+    ) -> Result<String, Err> {
         let header = Header::empty();
-        let claims = Claims::new(claims);
+        let claims = registered.to_claims(claims);
 
-        self.alg
-            .token(&header, &claims, &self.s_k)
-            .map_err(Err::TokenGenerationError)
+        match &self.s_k {
+            SigningKey::Rsa(s_k) => self.alg.rsa().token(&header, &claims, s_k.as_ref()),
+            SigningKey::Es256(s_k) => Es256.token(&header, &claims, s_k),
+            SigningKey::EdDsa(s_k) => Ed25519.token(&header, &claims, s_k),
+        }
+        .map_err(Err::TokenGenerationError)
     }
 }
 
 impl FromStr for Issuer {
     type Err = Err;
     fn from_str(jwk_str: &str) -> Result<Self, Self::Err> {
-        let jwk = serde_json::from_str::<JsonWebKey>(jwk_str)?;
-        let alg = Rsa::rs256();
-        let s_k = RsaPrivateKey::try_from(&jwk)?;
-        let p_k = RsaPublicKey::try_from(&jwk)?;
+        let (jwk, alg) = parse_jwk(jwk_str)?;
+        let s_k = match alg {
+            SigningAlgorithm::Rs256 | SigningAlgorithm::Ps256 => {
+                SigningKey::Rsa(Box::new(RsaPrivateKey::try_from(&jwk)?))
+            }
+            SigningAlgorithm::Es256 => SigningKey::Es256((&jwk).try_into()?),
+            SigningAlgorithm::EdDsa => SigningKey::EdDsa((&jwk).try_into()?),
+        };
+        let p_k = VerifyingKey::from_jwk(&jwk, alg)?;
         Ok(Self { alg, s_k, p_k })
     }
 }
 
 pub struct Validator {
-    alg: Rsa,
-    pub p_k: RsaPublicKey,
+    alg: SigningAlgorithm,
+    pub p_k: VerifyingKey,
+    clock: Option<DateTime<Utc>>,
 }
 
 impl Validator {
-    pub fn validate_token_integrity(&self, token: &str) -> Result<Token<CustomClaims>, Err> {
+    pub fn algorithm(&self) -> SigningAlgorithm {
+        self.alg
+    }
+
+    /// Enforces `exp` (which must then be present) and `nbf` (if present)
+    /// against the supplied time when validating tokens.
+    pub fn with_clock(mut self, now: DateTime<Utc>) -> Self {
+        self.clock = Some(now);
+        self
+    }
+
+    pub fn validate_token_integrity(&self, token: &str) -> Result<Token<TokenClaims>, Err> {
         let token: UntrustedToken = UntrustedToken::new(token).map_err(Err::TokenParseError)?;
 
-        self.alg
-            .validator(&self.p_k)
-            .validate(&token)
-            .map_err(Err::TokenValidationError)
+        let token: Token<TokenClaims> = match &self.p_k {
+            VerifyingKey::Rsa(p_k) => self.alg.rsa().validator(p_k).validate(&token),
+            VerifyingKey::Es256(p_k) => Es256.validator(p_k).validate(&token),
+            VerifyingKey::EdDsa(p_k) => Ed25519.validator(p_k).validate(&token),
+        }
+        .map_err(Err::TokenValidationError)?;
+
+        if let Some(now) = self.clock {
+            let time_options = TimeOptions::new(Duration::zero(), move || now);
+            token.claims().validate_expiration(&time_options)?;
+            if token.claims().not_before.is_some() {
+                token.claims().validate_maturity(&time_options)?;
+            }
+        }
+
+        Ok(token)
     }
 }
 
 impl FromStr for Validator {
     type Err = Err;
     fn from_str(jwk_str: &str) -> Result<Self, Self::Err> {
-        let jwk = serde_json::from_str::<JsonWebKey>(jwk_str)?;
-        let alg = Rsa::rs256();
-        let p_k = RsaPublicKey::try_from(&jwk)?;
-        Ok(Self { alg, p_k })
+        let (jwk, alg) = parse_jwk(jwk_str)?;
+        let p_k = VerifyingKey::from_jwk(&jwk, alg)?;
+        Ok(Self {
+            alg,
+            p_k,
+            clock: None,
+        })
     }
 }
 
@@ -214,6 +782,64 @@ mod tests {
     }
     "#;
 
+    const ES256_SECRET_KEY: &str = r#"
+    {
+      "kty": "EC",
+      "crv": "P-256",
+      "alg": "ES256",
+      "use": "sig",
+      "x": "nqqYqxl4GS_nmvRsZBrGOrMHXXRby1MQs5oHYlAB4NE",
+      "y": "JN2j0rJ2x-DooKUPJbSpXRxyYa1NXvEdrXeWWwBTOP8",
+      "d": "wdyZdBz6w0pYtzrP1Oq0GPaMBRF7uUDQ_xEnBtsp8lA"
+    }
+    "#;
+
+    const ES256_PUBLIC_KEY: &str = r#"
+    {
+      "kty": "EC",
+      "crv": "P-256",
+      "alg": "ES256",
+      "use": "sig",
+      "x": "nqqYqxl4GS_nmvRsZBrGOrMHXXRby1MQs5oHYlAB4NE",
+      "y": "JN2j0rJ2x-DooKUPJbSpXRxyYa1NXvEdrXeWWwBTOP8"
+    }
+    "#;
+
+    const EDDSA_SECRET_KEY: &str = r#"
+    {
+      "kty": "OKP",
+      "crv": "Ed25519",
+      "alg": "EdDSA",
+      "use": "sig",
+      "x": "OasNl_txIwm8IWTT2jWNe8H3MpmeHcvl20NgTxqeGvE",
+      "d": "vJ0WIE5v157cRyrmt6a8Q2PqmD9m6BI3OwxpSls9mjg"
+    }
+    "#;
+
+    const EDDSA_PUBLIC_KEY: &str = r#"
+    {
+      "kty": "OKP",
+      "crv": "Ed25519",
+      "alg": "EdDSA",
+      "use": "sig",
+      "x": "OasNl_txIwm8IWTT2jWNe8H3MpmeHcvl20NgTxqeGvE"
+    }
+    "#;
+
+    fn sign_and_validate(secret_key: &str, public_key: &str) -> Result<Token<TokenClaims>, Err> {
+        let mut claims = CustomClaims::new();
+        claims.add(
+            "supplier_did".to_string(),
+            "did:web:example.com".to_string(),
+        );
+
+        let iss = secret_key.parse::<Issuer>().unwrap();
+        let token = iss.generate_token(&claims).unwrap();
+
+        let validator = public_key.parse::<Validator>().unwrap();
+        validator.validate_token_integrity(&token)
+    }
+
     #[test]
     fn it_works() {
         let mut claims = CustomClaims::new();
@@ -234,7 +860,7 @@ mod tests {
                 .custom
                 .claims
                 .iter()
-                .find(|claim| claim.key == "supplier_did".to_string())
+                .find(|claim| claim.key == "supplier_did")
                 .unwrap()
                 .value,
             "did:web:example.com".to_string()
@@ -264,4 +890,366 @@ mod tests {
             "Token validation should fail, but it passed."
         );
     }
+
+    #[test]
+    fn it_selects_algorithm_from_jwk() {
+        assert_eq!(
+            PUBLIC_KEY.parse::<Validator>().unwrap().algorithm(),
+            SigningAlgorithm::Rs256
+        );
+        assert_eq!(
+            ES256_PUBLIC_KEY.parse::<Validator>().unwrap().algorithm(),
+            SigningAlgorithm::Es256
+        );
+        assert_eq!(
+            EDDSA_SECRET_KEY.parse::<Issuer>().unwrap().algorithm(),
+            SigningAlgorithm::EdDsa
+        );
+
+        let ps256_public_key = PUBLIC_KEY.replace("RS256", "PS256");
+        assert_eq!(
+            ps256_public_key.parse::<Validator>().unwrap().algorithm(),
+            SigningAlgorithm::Ps256
+        );
+    }
+
+    #[test]
+    fn it_works_with_ps256() {
+        let secret_key = SECRET_KEY.replace("RS256", "PS256");
+        let public_key = PUBLIC_KEY.replace("RS256", "PS256");
+        assert!(sign_and_validate(&secret_key, &public_key).is_ok());
+    }
+
+    #[test]
+    fn it_works_with_es256() {
+        let valid_token = sign_and_validate(ES256_SECRET_KEY, ES256_PUBLIC_KEY).unwrap();
+        assert_eq!(
+            valid_token.claims().custom.claims[0].value,
+            "did:web:example.com"
+        );
+    }
+
+    #[test]
+    fn it_works_with_eddsa() {
+        let valid_token = sign_and_validate(EDDSA_SECRET_KEY, EDDSA_PUBLIC_KEY).unwrap();
+        assert_eq!(
+            valid_token.claims().custom.claims[0].value,
+            "did:web:example.com"
+        );
+    }
+
+    #[test]
+    fn it_fails_on_algorithm_mismatch() {
+        assert!(sign_and_validate(ES256_SECRET_KEY, EDDSA_PUBLIC_KEY).is_err());
+        assert!(sign_and_validate(EDDSA_SECRET_KEY, PUBLIC_KEY).is_err());
+
+        let ps256_public_key = PUBLIC_KEY.replace("RS256", "PS256");
+        assert!(sign_and_validate(SECRET_KEY, &ps256_public_key).is_err());
+    }
+
+    #[test]
+    fn it_parses_algorithm_names() {
+        assert_eq!(
+            "ES256".parse::<SigningAlgorithm>().unwrap(),
+            SigningAlgorithm::Es256
+        );
+        assert_eq!(
+            "eddsa".parse::<SigningAlgorithm>().unwrap(),
+            SigningAlgorithm::EdDsa
+        );
+        assert!("HS256".parse::<SigningAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn it_rejects_unsupported_key() {
+        let rsa_with_ec_alg = PUBLIC_KEY.replace("RS256", "ES256");
+        assert!(matches!(
+            rsa_with_ec_alg.parse::<Validator>(),
+            Err(Err::UnsupportedAlgorithm(_))
+        ));
+
+        let hmac_key = r#"{"kty": "oct", "k": "c2VjcmV0"}"#;
+        assert!(matches!(
+            hmac_key.parse::<Validator>(),
+            Err(Err::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    fn licence_window() -> RegisteredClaims {
+        RegisteredClaims::new()
+            .issued_at("2025-01-01T00:00:00Z".parse().unwrap())
+            .not_before("2025-01-01T00:00:00Z".parse().unwrap())
+            .expires_at("2035-01-01T00:00:00Z".parse().unwrap())
+            .issuer("National_Mining_Authority")
+            .subject("ACME_Mining_Company")
+            .jwt_id("licence-0001")
+    }
+
+    #[test]
+    fn it_sets_registered_claims() {
+        let iss = SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = iss
+            .generate_token_with_registered_claims(&CustomClaims::new(), &licence_window())
+            .unwrap();
+
+        let validator = PUBLIC_KEY.parse::<Validator>().unwrap();
+        let valid_token = validator.validate_token_integrity(&token).unwrap();
+        let claims = valid_token.claims();
+
+        assert_eq!(
+            claims.expiration,
+            Some("2035-01-01T00:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            claims.not_before,
+            Some("2025-01-01T00:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            claims.issued_at,
+            Some("2025-01-01T00:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            claims.custom.iss.as_deref(),
+            Some("National_Mining_Authority")
+        );
+        assert_eq!(claims.custom.sub.as_deref(), Some("ACME_Mining_Company"));
+        assert_eq!(claims.custom.jti.as_deref(), Some("licence-0001"));
+    }
+
+    #[test]
+    fn it_enforces_validity_window_against_clock() {
+        let iss = SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = iss
+            .generate_token_with_registered_claims(&CustomClaims::new(), &licence_window())
+            .unwrap();
+
+        let validator = PUBLIC_KEY
+            .parse::<Validator>()
+            .unwrap()
+            .with_clock("2030-01-01T00:00:00Z".parse().unwrap());
+        assert!(validator.validate_token_integrity(&token).is_ok());
+
+        let validator = PUBLIC_KEY
+            .parse::<Validator>()
+            .unwrap()
+            .with_clock("2036-01-01T00:00:00Z".parse().unwrap());
+        assert!(matches!(
+            validator.validate_token_integrity(&token),
+            Err(Err::TokenValidationError(ValidationError::Expired))
+        ));
+
+        let validator = PUBLIC_KEY
+            .parse::<Validator>()
+            .unwrap()
+            .with_clock("2024-01-01T00:00:00Z".parse().unwrap());
+        assert!(matches!(
+            validator.validate_token_integrity(&token),
+            Err(Err::TokenValidationError(ValidationError::NotMature))
+        ));
+    }
+
+    #[test]
+    fn it_requires_expiry_when_clock_supplied() {
+        let iss = SECRET_KEY.parse::<Issuer>().unwrap();
+        let token = iss.generate_token(&CustomClaims::new()).unwrap();
+
+        let validator = PUBLIC_KEY.parse::<Validator>().unwrap();
+        assert!(validator.validate_token_integrity(&token).is_ok());
+
+        let validator = validator.with_clock("2030-01-01T00:00:00Z".parse().unwrap());
+        assert!(matches!(
+            validator.validate_token_integrity(&token),
+            Err(Err::TokenValidationError(ValidationError::NoClaim(_)))
+        ));
+    }
+
+    #[test]
+    fn it_keeps_each_predicate_once() {
+        let options = ProofOptions::default().predicates(
+            ["x>=20", "y<1", "x>=20.0", "x>=20"]
+                .iter()
+                .map(|predicate| predicate.parse().unwrap())
+                .collect(),
+        );
+        let statements: Vec<String> = options
+            .predicates
+            .iter()
+            .map(|predicate| predicate.to_string())
+            .collect();
+        assert_eq!(statements, ["x>=20", "y<1"]);
+    }
+
+    #[test]
+    fn it_derives_nullifiers_per_context() {
+        let iss = SECRET_KEY.parse::<Issuer>().unwrap();
+        let passport = iss.generate_token(&CustomClaims::new()).unwrap();
+        let other = iss
+            .generate_token_with_registered_claims(
+                &CustomClaims::new(),
+                &RegisteredClaims::new().jwt_id("other".to_string()),
+            )
+            .unwrap();
+
+        let nullifier_a = nullifier(&passport, "customer-a").unwrap();
+        assert_eq!(
+            nullifier(&format!("{passport}\n"), "customer-a").unwrap(),
+            nullifier_a
+        );
+        assert_ne!(nullifier(&passport, "customer-b").unwrap(), nullifier_a);
+        assert_ne!(nullifier(&other, "customer-a").unwrap(), nullifier_a);
+        assert_eq!(
+            BASE64_URL_SAFE_NO_PAD.decode(&nullifier_a).unwrap().len(),
+            32
+        );
+        assert!(nullifier("not a token", "customer-a").is_err());
+
+        // Whoever saw the claims knows the whole header and payload, which
+        // must not be enough to recompute the nullifier
+        let (signing_input, _) = passport.rsplit_once('.').unwrap();
+        let from_claims = Sha256::new()
+            .chain_update(b"product-passport-nullifier\0")
+            .chain_update(signing_input)
+            .chain_update(b"\0")
+            .chain_update("customer-a")
+            .finalize();
+        assert_ne!(BASE64_URL_SAFE_NO_PAD.encode(from_claims), nullifier_a);
+    }
+
+    #[test]
+    fn it_ignores_signature_malleation_in_nullifiers() {
+        let passport = ES256_SECRET_KEY
+            .parse::<Issuer>()
+            .unwrap()
+            .generate_token(&CustomClaims::new())
+            .unwrap();
+        // (r, n - s) is as valid a signature of the passport as (r, s)
+        let (input, signature) = passport.rsplit_once('.').unwrap();
+        let mut signature = BASE64_URL_SAFE_NO_PAD.decode(signature).unwrap();
+        negate_es256_s(&mut signature);
+        let malleated = format!("{input}.{}", BASE64_URL_SAFE_NO_PAD.encode(signature));
+        assert_ne!(malleated, passport);
+        assert!(ES256_PUBLIC_KEY
+            .parse::<Validator>()
+            .unwrap()
+            .validate_token_integrity(&malleated)
+            .is_ok());
+        assert_eq!(
+            nullifier(&malleated, "customer-a").unwrap(),
+            nullifier(&passport, "customer-a").unwrap()
+        );
+    }
+
+    #[test]
+    fn it_computes_rfc7638_thumbprints() {
+        // Example from RFC 7638 section 3.1
+        let rsa = r#"{
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+            "kid": "2011-04-29"
+        }"#;
+        assert_eq!(
+            jwk_thumbprint(rsa).unwrap(),
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+        );
+
+        // Private keys share the thumbprint of their public keys
+        for (sk, pk) in [
+            (ES256_SECRET_KEY, ES256_PUBLIC_KEY),
+            (EDDSA_SECRET_KEY, EDDSA_PUBLIC_KEY),
+        ] {
+            assert_eq!(jwk_thumbprint(sk).unwrap(), jwk_thumbprint(pk).unwrap());
+        }
+        assert_ne!(
+            jwk_thumbprint(ES256_PUBLIC_KEY).unwrap(),
+            jwk_thumbprint(EDDSA_PUBLIC_KEY).unwrap()
+        );
+
+        assert!(jwk_thumbprint(r#"{"kty": "EC", "crv": "P-256"}"#).is_err());
+    }
+
+    #[test]
+    fn it_digests_conflict_zones_canonically() {
+        let zones: ConflictZones = serde_json::from_str(
+            r#"{"version": 3, "zones": [
+                {"country": "GB", "region": "London"},
+                {"country": "GB", "region": "Cheshire"}
+            ]}"#,
+        )
+        .unwrap();
+        let reordered: ConflictZones = serde_json::from_str(
+            r#"{
+                "zones": [
+                    {"region": "Cheshire", "country": "GB"},
+                    {"country": "GB", "region": "London"},
+                    {"country": "GB", "region": "London"}
+                ],
+                "version": 3
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(zones.digest(), reordered.digest());
+
+        let next_version = ConflictZones {
+            version: 4,
+            zones: zones.zones.clone(),
+        };
+        assert_ne!(zones.digest(), next_version.digest());
+
+        let unversioned: ConflictZones =
+            serde_json::from_str(r#"{"zones": [{"country": "GB", "region": "London"}]}"#).unwrap();
+        assert_eq!(unversioned.version, 0);
+    }
+
+    #[test]
+    fn it_matches_country_region_and_excepted_zones() {
+        let zones: ConflictZones = serde_json::from_str(
+            r#"{"zones": [
+                {"country": "BOL", "region": "*"},
+                {"country": "GB", "region": "England", "except": ["Cornwall", "GB-LND"]},
+                {"country": "FR", "region": "Île-de-France"}
+            ]}"#,
+        )
+        .unwrap();
+        let zones = zones.normalised().unwrap();
+        assert_eq!(zones.zones[0].region, None);
+        assert_eq!(zones.zones[1].region.as_deref(), Some("GB-ENG"));
+        assert_eq!(zones.zones[1].except, ["GB-CON", "GB-LND"]);
+
+        let kind = |country, region| zones.find_match(country, region).map(|(_, kind)| kind);
+        assert_eq!(kind("BO", "BO-L"), Some(ZoneMatch::Country));
+        assert_eq!(kind("GB", "GB-CHE"), Some(ZoneMatch::Region));
+        assert_eq!(kind("GB", "GB-ENG"), Some(ZoneMatch::Region));
+        assert_eq!(kind("FR", "FR-75"), Some(ZoneMatch::Region));
+        assert_eq!(kind("GB", "GB-CON"), None);
+        assert_eq!(kind("GB", "GB-LND"), None);
+        assert_eq!(kind("GB", "GB-SCT"), None);
+        assert_eq!(kind("FR", "FR-13"), None);
+
+        // Exceptions must lie within the zone they are carved out of
+        let outside: ConflictZone =
+            serde_json::from_str(r#"{"country": "GB", "region": "GB-ENG", "except": ["GB-FIF"]}"#)
+                .unwrap();
+        assert_eq!(
+            outside.normalised(),
+            Err(CodeError::ExclusionOutsideZone {
+                region: "GB-FIF".to_string(),
+                zone: "GB-ENG".to_string()
+            })
+        );
+
+        // Exceptions are unordered in the digest
+        let reordered: ConflictZones = serde_json::from_str(
+            r#"{"zones": [{"country": "GB", "region": "GB-ENG", "except": ["GB-LND", "GB-CON"]}]}"#,
+        )
+        .unwrap();
+        let ordered: ConflictZones = serde_json::from_str(
+            r#"{"zones": [{"country": "GB", "region": "GB-ENG", "except": ["GB-CON", "GB-LND"]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(reordered.digest(), ordered.digest());
+        assert_eq!("area".parse(), Ok(ZoneMatch::Area));
+        assert_eq!(ZoneMatch::Country.to_string(), "country");
+    }
 }
@@ -0,0 +1,488 @@
+use std::str::FromStr;
+
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{parent_region, ConflictZone, ConflictZones, ZoneMatch};
+
+// Leaves and nodes are hashed with distinct prefixes, so that a node can never
+// be passed off as a leaf
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+type Hash = [u8; 32];
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MerkleError {
+    #[error("Conflict zone {0} has an area, which can only be checked against the full list")]
+    AreaInTree(String),
+
+    #[error("Conflict zone {0} is listed more than once with different exceptions")]
+    DuplicateZone(String),
+
+    #[error("Witness covers {found} regions, expected {expected}")]
+    WrongLength { expected: usize, found: usize },
+
+    #[error("Witness leaf is for '{found}', expected '{expected}'")]
+    WrongZone { expected: String, found: String },
+
+    #[error("Witness leaves are not adjacent on either side of '{0}'")]
+    NotAdjacent(String),
+
+    #[error("Witness does not lead to the conflict zone root")]
+    RootMismatch,
+
+    #[error("Malformed hash in witness: {0}")]
+    MalformedHash(String),
+
+    #[error("Malformed witness: {0}")]
+    MalformedWitness(String),
+}
+
+impl ConflictZone {
+    /// The key a zone is sorted by in a [`ZoneTree`]: its region, or its
+    /// country if it covers the whole country.  Region codes always contain a
+    /// hyphen, so the two never collide.
+    pub fn key(&self) -> &str {
+        self.region.as_deref().unwrap_or(&self.country)
+    }
+}
+
+fn leaf_hash(zone: &ConflictZone) -> Hash {
+    let json = serde_json::to_string(zone).expect("Could not serialise conflict zone");
+    Sha256::new()
+        .chain_update([LEAF_PREFIX])
+        .chain_update(json)
+        .finalize()
+        .into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    Sha256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+fn encode(hash: &Hash) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(hash)
+}
+
+fn decode(hash: &str) -> Result<Hash, MerkleError> {
+    BASE64_URL_SAFE_NO_PAD
+        .decode(hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| MerkleError::MalformedHash(hash.to_string()))
+}
+
+/// The keys a licence in `region` of `country` must be checked against: the
+/// whole country, the region and every region it lies within.
+pub fn zone_keys<'a>(country: &'a str, region: &'a str) -> Vec<&'a str> {
+    let mut keys = vec![country, region];
+    let mut region = region;
+    while let Some(parent) = parent_region(region) {
+        keys.push(parent);
+        region = parent;
+    }
+    keys
+}
+
+/// A Merkle tree over a normalised conflict zone list, with the zones sorted
+/// by [`ConflictZone::key`].  An odd node at the end of a level is carried up
+/// unchanged.  Areas cannot be located by key, so lists with areas must be
+/// published in full.
+#[derive(Debug, Clone)]
+pub struct ZoneTree {
+    zones: Vec<ConflictZone>,
+    /// Hashes of each level, from the leaves up to the root
+    levels: Vec<Vec<Hash>>,
+}
+
+impl ZoneTree {
+    pub fn build(conflict_zones: &ConflictZones) -> Result<Self, MerkleError> {
+        let mut zones = conflict_zones.zones.clone();
+        for zone in zones.iter_mut() {
+            if zone.area.is_some() {
+                return Err(MerkleError::AreaInTree(zone.key().to_string()));
+            }
+            zone.except.sort();
+            zone.except.dedup();
+        }
+        zones.sort_by(|a, b| a.key().cmp(b.key()).then_with(|| a.cmp(b)));
+        zones.dedup();
+        if let Some(pair) = zones.windows(2).find(|pair| pair[0].key() == pair[1].key()) {
+            return Err(MerkleError::DuplicateZone(pair[0].key().to_string()));
+        }
+
+        let mut levels = vec![zones.iter().map(leaf_hash).collect::<Vec<_>>()];
+        while levels[levels.len() - 1].len() > 1 {
+            let level = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [odd] => *odd,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(level);
+        }
+        Ok(Self { zones, levels })
+    }
+
+    pub fn len(&self) -> usize {
+        self.zones.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// URL-safe base64 root hash; the hash of nothing for an empty list.
+    pub fn root(&self) -> String {
+        match self.levels.last().and_then(|level| level.first()) {
+            Some(root) => encode(root),
+            None => encode(&Sha256::digest([]).into()),
+        }
+    }
+
+    fn proof(&self, index: usize) -> LeafProof {
+        let mut siblings = Vec::new();
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(encode(sibling));
+            }
+            position /= 2;
+        }
+        LeafProof {
+            zone: self.zones[index].clone(),
+            index,
+            siblings,
+        }
+    }
+
+    /// Places each of the [`zone_keys`] of a licence in the tree.
+    pub fn witness(&self, country: &str, region: &str) -> ZoneWitness {
+        let keys = zone_keys(country, region)
+            .into_iter()
+            .map(
+                |key| match self.zones.binary_search_by(|zone| zone.key().cmp(key)) {
+                    Ok(index) => KeyWitness::Member(self.proof(index)),
+                    Err(index) => KeyWitness::Absent {
+                        lower: index.checked_sub(1).map(|lower| self.proof(lower)),
+                        upper: (index < self.len()).then(|| self.proof(index)),
+                    },
+                },
+            )
+            .collect();
+        ZoneWitness { keys }
+    }
+}
+
+/// A zone in a [`ZoneTree`] with the sibling hashes on its path to the root.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LeafProof {
+    pub zone: ConflictZone,
+    pub index: usize,
+    pub siblings: Vec<String>,
+}
+
+impl LeafProof {
+    /// Checks that the zone is leaf `index` of a tree of `leaf_count` zones with
+    /// the given root.
+    fn verify(&self, root: &Hash, leaf_count: usize) -> Result<(), MerkleError> {
+        if self.index >= leaf_count {
+            return Err(MerkleError::RootMismatch);
+        }
+        let mut hash = leaf_hash(&self.zone);
+        let mut siblings = self.siblings.iter();
+        let (mut position, mut width) = (self.index, leaf_count);
+        while width > 1 {
+            // The last node of an odd level has no sibling and is carried up
+            if position ^ 1 < width {
+                let sibling = decode(siblings.next().ok_or(MerkleError::RootMismatch)?)?;
+                hash = if position % 2 == 0 {
+                    node_hash(&hash, &sibling)
+                } else {
+                    node_hash(&sibling, &hash)
+                };
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+        if siblings.next().is_some() || hash != *root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Where a key lies in a [`ZoneTree`]: at a zone, or strictly between two
+/// adjacent zones (or before the first or after the last).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum KeyWitness {
+    Member(LeafProof),
+    Absent {
+        lower: Option<LeafProof>,
+        upper: Option<LeafProof>,
+    },
+}
+
+/// Evidence of which zones of a [`ZoneTree`] could cover a licence, one
+/// [`KeyWitness`] for each of its [`zone_keys`] in order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ZoneWitness {
+    pub keys: Vec<KeyWitness>,
+}
+
+/// Parses a witness from JSON, as passed to the guest.
+impl FromStr for ZoneWitness {
+    type Err = MerkleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(|err| MerkleError::MalformedWitness(err.to_string()))
+    }
+}
+
+impl ZoneWitness {
+    /// Checks the witness against the `root` of a tree of `leaf_count` zones,
+    /// and returns how the licence's normalised `country` and `region` are
+    /// covered, if at all.  Only `O(log n)` hashes are computed per key.
+    pub fn check(
+        &self,
+        root: &str,
+        leaf_count: usize,
+        country: &str,
+        region: &str,
+    ) -> Result<Option<ZoneMatch>, MerkleError> {
+        let root = decode(root)?;
+        let keys = zone_keys(country, region);
+        if keys.len() != self.keys.len() {
+            return Err(MerkleError::WrongLength {
+                expected: keys.len(),
+                found: self.keys.len(),
+            });
+        }
+
+        let mut zone_match = None;
+        for (key, witness) in keys.into_iter().zip(&self.keys) {
+            match witness {
+                KeyWitness::Member(proof) => {
+                    if proof.zone.key() != key {
+                        return Err(MerkleError::WrongZone {
+                            expected: key.to_string(),
+                            found: proof.zone.key().to_string(),
+                        });
+                    }
+                    proof.verify(&root, leaf_count)?;
+                    zone_match = zone_match.or(proof.zone.matches(country, region));
+                }
+                KeyWitness::Absent { lower, upper } => {
+                    for proof in lower.iter().chain(upper) {
+                        proof.verify(&root, leaf_count)?;
+                    }
+                    let adjacent = match (lower, upper) {
+                        (Some(lower), Some(upper)) => {
+                            lower.zone.key() < key
+                                && key < upper.zone.key()
+                                && lower.index + 1 == upper.index
+                        }
+                        (None, Some(upper)) => key < upper.zone.key() && upper.index == 0,
+                        (Some(lower), None) => {
+                            lower.zone.key() < key && lower.index + 1 == leaf_count
+                        }
+                        (None, None) => leaf_count == 0,
+                    };
+                    if !adjacent {
+                        return Err(MerkleError::NotAdjacent(key.to_string()));
+                    }
+                }
+            }
+        }
+        Ok(zone_match)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_tree(json: &str) -> ZoneTree {
+        let zones: ConflictZones = serde_json::from_str(json).unwrap();
+        ZoneTree::build(&zones.normalised().unwrap()).unwrap()
+    }
+
+    fn check(
+        tree: &ZoneTree,
+        witness: &ZoneWitness,
+        region: &str,
+    ) -> Result<Option<ZoneMatch>, MerkleError> {
+        witness.check(&tree.root(), tree.len(), &region[..2], region)
+    }
+
+    #[test]
+    fn it_proves_membership_and_absence() {
+        let tree = build_tree(
+            r#"{"zones": [
+                {"country": "GB", "region": "GB-WAR"},
+                {"country": "GB", "region": "GB-LND"},
+                {"country": "GB", "region": "GB-SCT", "except": ["GB-FIF"]},
+                {"country": "BO"},
+                {"country": "FR", "region": "FR-IDF"}
+            ]}"#,
+        );
+        assert_eq!(tree.len(), 5);
+
+        for (region, expected) in [
+            ("GB-CON", None),
+            ("GB-WAR", Some(ZoneMatch::Region)),
+            ("GB-GLG", Some(ZoneMatch::Region)),
+            ("GB-FIF", None),
+            ("BO-L", Some(ZoneMatch::Country)),
+            ("FR-75", Some(ZoneMatch::Region)),
+            ("FR-13", None),
+            // Before the first and after the last zone
+            ("AD-02", None),
+            ("ZW-MA", None),
+        ] {
+            let witness = tree.witness(&region[..2], region);
+            assert_eq!(check(&tree, &witness, region), Ok(expected), "{region}");
+        }
+    }
+
+    #[test]
+    fn it_rejects_forged_witnesses() {
+        let tree = build_tree(
+            r#"{"zones": [
+                {"country": "GB", "region": "GB-CON"},
+                {"country": "GB", "region": "GB-LND"},
+                {"country": "GB", "region": "GB-WAR"}
+            ]}"#,
+        );
+
+        // Skipping over the zone that covers the licence
+        let mut witness = tree.witness("GB", "GB-CON");
+        let KeyWitness::Member(member) = &witness.keys[1] else {
+            panic!("GB-CON should be a member")
+        };
+        let lower = member.index.checked_sub(1).map(|index| tree.proof(index));
+        witness.keys[1] = KeyWitness::Absent {
+            lower,
+            upper: Some(tree.proof(member.index + 1)),
+        };
+        assert_eq!(
+            check(&tree, &witness, "GB-CON"),
+            Err(MerkleError::NotAdjacent("GB-CON".to_string()))
+        );
+
+        // Claiming the tree is empty
+        witness.keys[1] = KeyWitness::Absent {
+            lower: None,
+            upper: None,
+        };
+        assert!(check(&tree, &witness, "GB-CON").is_err());
+
+        // Altering the zone in a leaf
+        let mut witness = tree.witness("GB", "GB-CON");
+        if let KeyWitness::Member(member) = &mut witness.keys[1] {
+            member.zone.except.push("GB-CON".to_string());
+        }
+        assert_eq!(
+            check(&tree, &witness, "GB-CON"),
+            Err(MerkleError::RootMismatch)
+        );
+
+        // Leaving out the ancestor regions
+        let mut witness = tree.witness("GB", "GB-CON");
+        witness.keys.truncate(2);
+        assert!(matches!(
+            check(&tree, &witness, "GB-CON"),
+            Err(MerkleError::WrongLength { .. })
+        ));
+
+        // Against another list
+        let other = build_tree(r#"{"zones": [{"country": "GB", "region": "GB-WAR"}]}"#);
+        let witness = other.witness("GB", "GB-CON");
+        assert_eq!(
+            check(&tree, &witness, "GB-CON"),
+            Err(MerkleError::RootMismatch)
+        );
+    }
+
+    #[test]
+    fn it_builds_canonical_trees() {
+        let reordered = build_tree(
+            r#"{"zones": [
+                {"country": "GB", "region": "GB-ENG", "except": ["GB-LND", "GB-CON"]},
+                {"country": "GB", "region": "GB-WAR"},
+                {"country": "GB", "region": "GB-WAR"}
+            ]}"#,
+        );
+        let ordered = build_tree(
+            r#"{"zones": [
+                {"country": "GB", "region": "GB-WAR"},
+                {"country": "GB", "region": "GB-ENG", "except": ["GB-CON", "GB-LND"]}
+            ]}"#,
+        );
+        assert_eq!(reordered.root(), ordered.root());
+        assert_eq!(ordered.len(), 2);
+
+        let empty = build_tree(r#"{"zones": []}"#);
+        assert!(empty.is_empty());
+        assert_eq!(
+            check(&empty, &empty.witness("GB", "GB-CON"), "GB-CON"),
+            Ok(None)
+        );
+
+        let zones: ConflictZones = serde_json::from_str(
+            r#"{"zones": [
+                {"country": "GB", "region": "GB-ENG"},
+                {"country": "GB", "region": "GB-ENG", "except": ["GB-CON"]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ZoneTree::build(&zones).unwrap_err(),
+            MerkleError::DuplicateZone("GB-ENG".to_string())
+        );
+        let zones: ConflictZones =
+            serde_json::from_str(include_str!("../../test_data/conflict_zones.json")).unwrap();
+        assert!(matches!(
+            ZoneTree::build(&zones),
+            Err(MerkleError::AreaInTree(_))
+        ));
+    }
+
+    #[test]
+    fn it_scales_logarithmically() {
+        let zones = ConflictZones {
+            version: 1,
+            zones: crate::iso3166::subdivisions()
+                .filter(|(code, _, _)| !code.starts_with("GB"))
+                .map(|(code, _, _)| ConflictZone {
+                    country: code[..2].to_string(),
+                    region: Some(code.to_string()),
+                    except: Vec::new(),
+                    area: None,
+                })
+                .collect(),
+        };
+        let tree = ZoneTree::build(&zones).unwrap();
+        let witness = tree.witness("GB", "GB-CON");
+        assert_eq!(check(&tree, &witness, "GB-CON"), Ok(None));
+        let depth = usize::BITS - tree.len().leading_zeros();
+        assert!(witness.keys.iter().all(|key| match key {
+            KeyWitness::Absent {
+                lower: Some(lower),
+                upper: Some(upper),
+            } => lower.siblings.len() as u32 <= depth && upper.siblings.len() as u32 <= depth,
+            _ => false,
+        }));
+    }
+}
@@ -0,0 +1,297 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::CustomClaims;
+
+/// Decimal places numbers are compared to.  Claims are compared exactly as
+/// fixed-point integers, never as floats, so a proof cannot depend on rounding.
+const FRACTION_DIGITS: u32 = 18;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PredicateError {
+    #[error("Predicate '{0}' is not of the form CLAIM<BOUND, CLAIM<=BOUND, CLAIM=BOUND, CLAIM>=BOUND or CLAIM>BOUND")]
+    Malformed(String),
+
+    #[error("Bound '{0}' is not a decimal number")]
+    InvalidBound(String),
+
+    #[error("Missing claim '{0}'")]
+    MissingClaim(String),
+
+    #[error("Claim '{claim}' is not a decimal number: '{value}'")]
+    NotANumber { claim: String, value: String },
+}
+
+/// How a claim is compared against a bound.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparator {
+    LessThan,
+    AtMost,
+    Equal,
+    AtLeast,
+    GreaterThan,
+}
+
+impl Comparator {
+    fn symbol(&self) -> &'static str {
+        match self {
+            Comparator::LessThan => "<",
+            Comparator::AtMost => "<=",
+            Comparator::Equal => "=",
+            Comparator::AtLeast => ">=",
+            Comparator::GreaterThan => ">",
+        }
+    }
+}
+
+/// A statement about a numeric passport claim, e.g.
+/// `recycled_content_percent>=20`, of which the guest commits only the
+/// statement and whether it holds.  Ranges are two predicates on one claim.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Predicate {
+    pub claim: String,
+    pub comparator: Comparator,
+    /// Decimal number, e.g. `20` or `8.5`
+    pub bound: String,
+}
+
+impl FromStr for Predicate {
+    type Err = PredicateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let start = s
+            .find(['<', '=', '>'])
+            .ok_or_else(|| PredicateError::Malformed(s.to_string()))?;
+        let (claim, rest) = s.split_at(start);
+        let (comparator, bound) = [
+            Comparator::AtMost,
+            Comparator::AtLeast,
+            Comparator::LessThan,
+            Comparator::Equal,
+            Comparator::GreaterThan,
+        ]
+        .into_iter()
+        .find_map(|comparator| {
+            rest.strip_prefix(comparator.symbol())
+                .map(|bound| (comparator, bound))
+        })
+        .ok_or_else(|| PredicateError::Malformed(s.to_string()))?;
+
+        let (claim, bound) = (claim.trim(), bound.trim());
+        if claim.is_empty() {
+            return Err(PredicateError::Malformed(s.to_string()));
+        }
+        parse_decimal(bound).ok_or_else(|| PredicateError::InvalidBound(bound.to_string()))?;
+        Ok(Self {
+            claim: claim.to_string(),
+            comparator,
+            bound: bound.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for Predicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.claim,
+            self.comparator.symbol(),
+            self.bound
+        )
+    }
+}
+
+impl Predicate {
+    /// Whether the predicate holds for `claims`.
+    pub fn evaluate(&self, claims: &CustomClaims) -> Result<bool, PredicateError> {
+        let value = claims
+            .get(&self.claim)
+            .ok_or_else(|| PredicateError::MissingClaim(self.claim.clone()))?;
+        let value = parse_decimal(value).ok_or_else(|| PredicateError::NotANumber {
+            claim: self.claim.clone(),
+            value: value.to_string(),
+        })?;
+        let bound = parse_decimal(&self.bound)
+            .ok_or_else(|| PredicateError::InvalidBound(self.bound.clone()))?;
+        Ok(match self.comparator {
+            Comparator::LessThan => value < bound,
+            Comparator::AtMost => value <= bound,
+            Comparator::Equal => value == bound,
+            Comparator::AtLeast => value >= bound,
+            Comparator::GreaterThan => value > bound,
+        })
+    }
+
+    /// Key under which the guest commits whether the predicate holds.  The
+    /// bound is written canonically, so `x>=20` and `x>=20.0` share a key.
+    pub fn journal_key(&self) -> String {
+        let bound = parse_decimal(&self.bound).map_or_else(|| self.bound.clone(), format_decimal);
+        format!(
+            "predicate:{}{}{}",
+            self.claim,
+            self.comparator.symbol(),
+            bound
+        )
+    }
+}
+
+/// Parses a plain decimal number such as `-8.25` into units of
+/// 10^-[`FRACTION_DIGITS`].  Exponents, signs other than a leading `-` and
+/// values that do not fit are rejected.
+fn parse_decimal(number: &str) -> Option<i128> {
+    let (negative, digits) = match number.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, number),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if integer.is_empty() && fraction.is_empty()
+        || fraction.len() > FRACTION_DIGITS as usize
+        || !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut value: i128 = 0;
+    for digit in integer.bytes().chain(fraction.bytes()) {
+        value = value.checked_mul(10)?.checked_add((digit - b'0') as i128)?;
+    }
+    value = value.checked_mul(10i128.pow(FRACTION_DIGITS - fraction.len() as u32))?;
+    Some(if negative { -value } else { value })
+}
+
+/// Formats units of 10^-[`FRACTION_DIGITS`] as the shortest plain decimal,
+/// e.g. `8.25`, `20` or `-0.5`.
+fn format_decimal(value: i128) -> String {
+    let scale = 10u128.pow(FRACTION_DIGITS);
+    let sign = if value < 0 { "-" } else { "" };
+    let (integer, fraction) = (value.unsigned_abs() / scale, value.unsigned_abs() % scale);
+    if fraction == 0 {
+        return format!("{sign}{integer}");
+    }
+    let fraction = format!("{fraction:0width$}", width = FRACTION_DIGITS as usize);
+    format!("{sign}{integer}.{}", fraction.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_predicates() {
+        let predicate: Predicate = "recycled_content_percent >= 20".parse().unwrap();
+        assert_eq!(predicate.claim, "recycled_content_percent");
+        assert_eq!(predicate.comparator, Comparator::AtLeast);
+        assert_eq!(predicate.bound, "20");
+        assert_eq!(predicate.to_string(), "recycled_content_percent>=20");
+        assert_eq!(
+            predicate.journal_key(),
+            "predicate:recycled_content_percent>=20"
+        );
+        assert_eq!(
+            "quantity_kg<500".parse::<Predicate>().unwrap().comparator,
+            Comparator::LessThan
+        );
+        // One statement has one key, however its bound is written
+        for equivalent in [
+            "recycled_content_percent>=20.0",
+            "recycled_content_percent>=020",
+            "recycled_content_percent >= 20.000",
+        ] {
+            assert_eq!(
+                equivalent.parse::<Predicate>().unwrap().journal_key(),
+                predicate.journal_key()
+            );
+        }
+        assert_eq!(
+            "carbon_kg_per_kg<=.50"
+                .parse::<Predicate>()
+                .unwrap()
+                .journal_key(),
+            "predicate:carbon_kg_per_kg<=0.5"
+        );
+
+        assert!(matches!(
+            "recycled_content_percent".parse::<Predicate>(),
+            Err(PredicateError::Malformed(_))
+        ));
+        assert!(matches!(
+            ">=20".parse::<Predicate>(),
+            Err(PredicateError::Malformed(_))
+        ));
+        assert_eq!(
+            "carbon_kg_per_kg<=1e3".parse::<Predicate>(),
+            Err(PredicateError::InvalidBound("1e3".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_evaluates_predicates_exactly() {
+        let mut claims = CustomClaims::new();
+        claims.add("recycled_content_percent".to_string(), "20".to_string());
+        claims.add(
+            "carbon_kg_per_kg".to_string(),
+            "8.000000000000000001".to_string(),
+        );
+        claims.add("product".to_string(), "Lithium".to_string());
+        let holds = |predicate: &str| predicate.parse::<Predicate>().unwrap().evaluate(&claims);
+
+        assert_eq!(holds("recycled_content_percent>=20"), Ok(true));
+        assert_eq!(holds("recycled_content_percent>20"), Ok(false));
+        assert_eq!(holds("recycled_content_percent=20.0"), Ok(true));
+        assert_eq!(holds("recycled_content_percent>-5"), Ok(true));
+        // Floats would round this to 8
+        assert_eq!(holds("carbon_kg_per_kg<=8"), Ok(false));
+        assert_eq!(holds("carbon_kg_per_kg<=8.5"), Ok(true));
+
+        assert!(matches!(
+            holds("product>=1"),
+            Err(PredicateError::NotANumber { .. })
+        ));
+        assert_eq!(
+            holds("quantity_kg>=100"),
+            Err(PredicateError::MissingClaim("quantity_kg".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_parses_decimals() {
+        assert_eq!(parse_decimal("0"), Some(0));
+        assert_eq!(parse_decimal("1.5"), Some(15 * 10i128.pow(17)));
+        assert_eq!(parse_decimal(".5"), parse_decimal("0.50"));
+        assert_eq!(parse_decimal("-2"), Some(-2 * 10i128.pow(18)));
+        for invalid in [
+            "",
+            ".",
+            "-",
+            "+1",
+            "1e3",
+            "NaN",
+            "1.2.3",
+            " 1",
+            "0.0000000000000000001",
+        ] {
+            assert_eq!(parse_decimal(invalid), None, "{invalid}");
+        }
+        assert_eq!(parse_decimal(&"9".repeat(40)), None);
+    }
+
+    #[test]
+    fn it_formats_decimals() {
+        for (number, formatted) in [
+            ("0", "0"),
+            ("-0.0", "0"),
+            ("20.00", "20"),
+            ("8.25", "8.25"),
+            ("-2.5", "-2.5"),
+            ("0.000000000000000001", "0.000000000000000001"),
+        ] {
+            assert_eq!(format_decimal(parse_decimal(number).unwrap()), formatted);
+        }
+    }
+}
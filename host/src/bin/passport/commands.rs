@@ -0,0 +1,1207 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use chrono::{DateTime, Duration, Utc};
+use clap::{Args, Subcommand};
+use host::{
+    commitment_opens, compute_fingerprint, conflict_zone_authority_matches, conflict_zones_match,
+    conflict_zones_version_at_least, generate_key_pair, licence_status_fresh,
+    mining_authority_trusted, predicate_holds, prove_token_validation, recall_list_fresh,
+    ConflictZoneInput, NullifierStore, Policy, PolicyReport, ProveError, TrustRegistry, Verdict,
+};
+use jwt_compact::UntrustedToken;
+use jwt_core::{
+    disclosure_salt, token_digest, ConflictZoneList, ConflictZoneRoot, Coordinate, CustodyTransfer,
+    CustomClaims, DisclosureSet, Issuer, LicenceStatusList, MiningLicence, Predicate,
+    ProductPassport, ProofOptions, PublicOutput, RecallList, RegisteredClaims, SigningAlgorithm,
+    SourceShare, TokenClaims, PASSPORT_CLAIMS,
+};
+use methods::VERIFY_TOKEN_WITH_SOME_KEY_ID;
+use risc0_zkvm::Receipt;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Write a claims file from key/value pairs
+    Claims(ClaimsArgs),
+
+    /// Sign a credential
+    #[command(subcommand)]
+    Sign(SignCommand),
+
+    /// Generate a zero-knowledge product passport
+    Prove(ProveArgs),
+
+    /// Verify a zero-knowledge product passport
+    Verify(VerifyArgs),
+
+    /// Print the fingerprint of a public key
+    Fingerprint(FingerprintArgs),
+
+    /// Generate a new signing key pair
+    Keygen(KeygenArgs),
+
+    /// Print the contents of a token or receipt without verifying it
+    Inspect(InspectArgs),
+
+    /// Run the interactive terminal interface
+    Tui,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SignCommand {
+    /// Sign an arbitrary claims file
+    Claims(SignClaimsArgs),
+
+    /// Sign a mining licence for a mining company
+    Licence(SignLicenceArgs),
+
+    /// Sign a product passport for a shipment
+    Passport(SignPassportArgs),
+
+    /// Sign a list of recalled product passports
+    RecallList(SignRecallListArgs),
+
+    /// Sign a licence status list, revoking licences by their status index
+    LicenceStatus(SignLicenceStatusArgs),
+
+    /// Sign a conflict zone list
+    ConflictZones(SignConflictZonesArgs),
+
+    /// Sign a transfer of custody of a shipment
+    CustodyTransfer(SignCustodyTransferArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ClaimsArgs {
+    /// Path in which to save the claims file
+    #[arg(short, long, default_value = "./claims.json")]
+    output: String,
+
+    /// Claim given as `key=value`
+    #[arg(long = "claim", value_name = "KEY=VALUE")]
+    claims: Vec<String>,
+
+    /// Claim given as `key=path`, where the value is read from the file
+    #[arg(long = "file-claim", value_name = "KEY=PATH")]
+    file_claims: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct SigningArgs {
+    /// Path to signing key
+    #[arg(short, long)]
+    signing_key: String,
+
+    /// Path in which to save the token
+    #[arg(short, long)]
+    output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct SignClaimsArgs {
+    #[command(flatten)]
+    signing: SigningArgs,
+
+    /// Path to claims file
+    #[arg(short, long)]
+    claims: String,
+
+    /// Expiry time (`exp`) as an RFC 3339 timestamp
+    #[arg(long)]
+    expires_at: Option<DateTime<Utc>>,
+
+    /// Time before which the token is not valid (`nbf`) as an RFC 3339 timestamp
+    #[arg(long)]
+    not_before: Option<DateTime<Utc>>,
+
+    /// Issuer of the token (`iss`)
+    #[arg(long)]
+    issuer: Option<String>,
+
+    /// Subject of the token (`sub`)
+    #[arg(long)]
+    subject: Option<String>,
+
+    /// Unique identifier of the token (`jti`)
+    #[arg(long)]
+    jwt_id: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct SignLicenceArgs {
+    #[command(flatten)]
+    signing: SigningArgs,
+
+    /// Identifier of the issuing authority
+    #[arg(long)]
+    issuer_id: String,
+
+    /// Identifier of the mining company
+    #[arg(long)]
+    subject_id: String,
+
+    /// Path to the mining company's public key
+    #[arg(long)]
+    subject_pk: String,
+
+    /// Start of the licence as an RFC 3339 timestamp
+    #[arg(long)]
+    valid_from: DateTime<Utc>,
+
+    /// End of the licence as an RFC 3339 timestamp
+    #[arg(long)]
+    valid_to: DateTime<Utc>,
+
+    /// Country the mining company operates in, as an ISO 3166-1 code or name
+    #[arg(long)]
+    country: String,
+
+    /// Region the mining company operates in, as an ISO 3166-2 code or name
+    #[arg(long)]
+    region: String,
+
+    /// Position of a licensed mine site as LON,LAT in degrees; may be repeated
+    #[arg(long = "site", value_name = "LON,LAT", allow_hyphen_values = true)]
+    sites: Vec<Coordinate>,
+
+    /// Position of the licence in the authority's licence status list
+    #[arg(long)]
+    status_index: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+pub struct SignPassportArgs {
+    #[command(flatten)]
+    signing: SigningArgs,
+
+    /// Product in the shipment
+    #[arg(long)]
+    product: String,
+
+    /// Issue date as an RFC 3339 timestamp
+    #[arg(long)]
+    issue_date: DateTime<Utc>,
+
+    /// Identifier of the shipment (a random UUID if omitted)
+    #[arg(long)]
+    shipment_id: Option<String>,
+
+    /// Licence a blended shipment was partly sourced under, as the path to
+    /// the licence token and the mass in kilograms; may be repeated
+    #[arg(long = "source", value_name = "PATH=MASS_KG", value_parser = parse_source, requires = "declared_mass_kg")]
+    sources: Vec<(String, u64)>,
+
+    /// Declared mass of the shipment in kilograms, which the sources of a
+    /// blended shipment must add up to
+    #[arg(long)]
+    declared_mass_kg: Option<u64>,
+
+    /// Further claim about the shipment, e.g. `recycled_content_percent=22.5`;
+    /// may be repeated
+    #[arg(long = "claim", value_name = "KEY=VALUE")]
+    claims: Vec<String>,
+}
+
+fn parse_source(source: &str) -> Result<(String, u64), String> {
+    let (path, mass) = source
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected PATH=MASS_KG, got '{source}'"))?;
+    let mass = mass
+        .parse()
+        .map_err(|err| format!("invalid mass '{mass}': {err}"))?;
+    Ok((path.to_string(), mass))
+}
+
+#[derive(Args, Debug)]
+pub struct SignRecallListArgs {
+    #[command(flatten)]
+    signing: SigningArgs,
+
+    /// Identifier of the mining company
+    #[arg(long)]
+    issuer_id: String,
+
+    /// Publication date as an RFC 3339 timestamp
+    #[arg(long)]
+    publication_date: DateTime<Utc>,
+
+    /// Identifier of a recalled shipment; may be repeated
+    #[arg(long = "shipment-id", value_name = "SHIPMENT_ID")]
+    shipment_ids: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct SignLicenceStatusArgs {
+    #[command(flatten)]
+    signing: SigningArgs,
+
+    /// Identifier of the issuing authority
+    #[arg(long)]
+    issuer_id: String,
+
+    /// Publication date as an RFC 3339 timestamp
+    #[arg(long)]
+    publication_date: DateTime<Utc>,
+
+    /// Number of licences the list has room for
+    #[arg(long, default_value_t = 131_072)]
+    size: u64,
+
+    /// Status index of a revoked licence; may be repeated
+    #[arg(long = "revoke", value_name = "INDEX")]
+    revoked: Vec<u64>,
+}
+
+#[derive(Args, Debug)]
+pub struct SignConflictZonesArgs {
+    #[command(flatten)]
+    signing: SigningArgs,
+
+    /// Identifier of the publishing authority
+    #[arg(long)]
+    publisher_id: String,
+
+    /// Publication date as an RFC 3339 timestamp
+    #[arg(long)]
+    publication_date: DateTime<Utc>,
+
+    /// Path to conflict zones JSON file
+    #[arg(long)]
+    conflict_zones: String,
+
+    /// Sign only the Merkle root of the list, for lists too large to prove
+    /// against in full; zones may not have areas
+    #[arg(long)]
+    root: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SignCustodyTransferArgs {
+    #[command(flatten)]
+    signing: SigningArgs,
+
+    /// Identifier of the shipment
+    #[arg(long)]
+    shipment_id: String,
+
+    /// Identifier of the current holder
+    #[arg(long)]
+    issuer_id: String,
+
+    /// Identifier of the next holder
+    #[arg(long)]
+    subject_id: String,
+
+    /// Path to the next holder's public key
+    #[arg(long)]
+    subject_pk: String,
+
+    /// Transfer date as an RFC 3339 timestamp
+    #[arg(long)]
+    transfer_date: DateTime<Utc>,
+}
+
+#[derive(Args, Debug)]
+pub struct ProveArgs {
+    /// Path to product passport
+    #[arg(long)]
+    passport: String,
+
+    /// Path to mining licence; repeated for each source of a blended shipment
+    #[arg(short, long = "licence", required = true)]
+    licences: Vec<String>,
+
+    /// Path to national mining authority verification key
+    #[arg(long)]
+    mining_authority_pk: String,
+
+    /// Path to signed conflict zone list, or to a signed root if `--conflict-zone-tree` is given
+    #[arg(short, long)]
+    conflict_zones: String,
+
+    /// Path to the conflict zones JSON file behind a signed root; the guest
+    /// then only reads the zones either side of the licence's regions
+    #[arg(long)]
+    conflict_zone_tree: Option<String>,
+
+    /// Path to conflict zone authority verification key
+    #[arg(long)]
+    conflict_zone_authority_pk: String,
+
+    /// Paths to custody transfer tokens, in order from the mining company to the final holder
+    #[arg(long, num_args = 0..)]
+    custody_tokens: Vec<String>,
+
+    /// Commit the full public keys to the journal as well as their fingerprints
+    #[arg(long)]
+    commit_full_keys: bool,
+
+    /// Fail instead of proving if the passport was issued outside the licence's validity period
+    #[arg(long)]
+    strict: bool,
+
+    /// Path to a signed licence status list; the proof fails if any licence is revoked
+    #[arg(long)]
+    licence_status_list: Option<String>,
+
+    /// Path to the mining company's latest signed recall list; the proof fails
+    /// if the passport is on it
+    #[arg(long)]
+    recall_list: Option<String>,
+
+    /// Context chosen by the verifier, e.g. a customer ID, in which to commit a
+    /// nullifier of the passport so that reuse can be detected
+    #[arg(long)]
+    nullifier_context: Option<String>,
+
+    /// Passport claim to reveal in the journal; may be repeated.  Giving any of
+    /// `--reveal`, `--commit` or `--hide` requires every claim to be listed once.
+    #[arg(long = "reveal", value_name = "KEY")]
+    revealed_claims: Vec<String>,
+
+    /// Passport claim to commit to as a salted hash, which can be opened later
+    /// with the salt printed here; may be repeated
+    #[arg(long = "commit", value_name = "KEY")]
+    committed_claims: Vec<String>,
+
+    /// Passport claim to leave out of the journal; may be repeated
+    #[arg(long = "hide", value_name = "KEY")]
+    hidden_claims: Vec<String>,
+
+    /// Statement about a numeric passport claim to prove, such as
+    /// `recycled_content_percent>=20`, without revealing the claim; may be repeated
+    #[arg(long = "predicate", value_name = "CLAIM<OP><BOUND>", value_parser = parse_predicate)]
+    predicates: Vec<Predicate>,
+
+    /// Path in which to save the receipt
+    #[arg(short, long, default_value = "./receipt.bin")]
+    receipt: String,
+}
+
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    /// Path to receipt
+    receipt: String,
+
+    /// Path to the conflict zones JSON file the proof must have been generated against
+    #[arg(long)]
+    conflict_zones: Option<String>,
+
+    /// Path to the key of the conflict zone authority that must have signed the list
+    #[arg(long)]
+    trusted_conflict_zone_authority_pk: Option<String>,
+
+    /// Fingerprint of a trusted national mining authority key; may be repeated
+    #[arg(long = "trusted-mining-authority", value_name = "FINGERPRINT")]
+    trusted_mining_authorities: Vec<String>,
+
+    /// Path to a trust registry; the receipt is rejected unless both authority keys
+    /// are in it and were trusted at the time
+    #[arg(long)]
+    registry: Option<String>,
+
+    /// Path to a policy file (TOML, or JSON if it ends in `.json`) whose rules the
+    /// committed claims must satisfy
+    #[arg(long)]
+    policy: Option<String>,
+
+    /// Minimum accepted version of the conflict zone list
+    #[arg(long)]
+    min_conflict_zones_version: Option<u32>,
+
+    /// Maximum age in days of the licence status list the licences were checked against
+    #[arg(long)]
+    max_licence_status_age_days: Option<i64>,
+
+    /// Maximum age in days of the recall list the passport was checked against
+    #[arg(long)]
+    max_recall_list_age_days: Option<i64>,
+
+    /// Path to a nullifier store, created if missing; the receipt is rejected if
+    /// its nullifier is already recorded, and recorded otherwise
+    #[arg(long, requires = "nullifier_context")]
+    nullifier_store: Option<String>,
+
+    /// Context the receipt's nullifier must have been committed in
+    #[arg(long, requires = "nullifier_store")]
+    nullifier_context: Option<String>,
+
+    /// Opening of a committed passport claim, as given by the prover; may be repeated
+    #[arg(long = "opening", value_name = "KEY=SALT:VALUE")]
+    openings: Vec<String>,
+
+    /// Statement about a numeric passport claim that must have been proved to hold;
+    /// may be repeated
+    #[arg(long = "require-predicate", value_name = "CLAIM<OP><BOUND>", value_parser = parse_predicate)]
+    required_predicates: Vec<Predicate>,
+}
+
+fn parse_predicate(predicate: &str) -> Result<Predicate, String> {
+    predicate
+        .parse()
+        .map_err(|err: jwt_core::PredicateError| err.to_string())
+}
+
+#[derive(Args, Debug)]
+pub struct FingerprintArgs {
+    /// Path to public key
+    public_key: String,
+}
+
+#[derive(Args, Debug)]
+pub struct KeygenArgs {
+    /// Signature algorithm of the key pair (RS256, PS256, ES256 or EdDSA)
+    #[arg(short, long, default_value = "ES256", value_parser = parse_algorithm)]
+    algorithm: SigningAlgorithm,
+
+    /// Name of the key pair, saved as `<NAME>_sk.jwk` and `<NAME>_pk.jwk`
+    #[arg(short, long)]
+    name: String,
+
+    /// Directory in which to save the key pair
+    #[arg(short, long, default_value = ".")]
+    output_dir: PathBuf,
+}
+
+fn parse_algorithm(alg: &str) -> Result<SigningAlgorithm, String> {
+    alg.parse().map_err(|err: jwt_core::Err| err.to_string())
+}
+
+#[derive(Args, Debug)]
+pub struct InspectArgs {
+    /// Path to a token or receipt
+    path: String,
+}
+
+/// Output of a command, printed as text or JSON depending on `--json`.
+pub struct Report {
+    pub text: String,
+    pub json: Value,
+}
+
+/// Failure of a command.  Each variant has its own exit code; clap uses 2 for
+/// usage errors.
+#[derive(Error, Debug)]
+pub enum CliError {
+    /// The proof, credential or check was rejected (exit code 1)
+    #[error("Rejected: {0}")]
+    Rejected(String),
+
+    /// An input could not be read or parsed (exit code 3)
+    #[error("Invalid input: {0}")]
+    Input(String),
+
+    /// The prover itself failed (exit code 4)
+    #[error("Prover failed: {0}")]
+    Prover(String),
+
+    /// An authority key is not in the trust registry (exit code 1)
+    #[error("Rejected: {0}")]
+    Untrusted(Verdict),
+
+    /// The committed claims do not satisfy the policy (exit code 1)
+    #[error("Rejected by policy:\n{0}")]
+    PolicyViolated(PolicyReport),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::Rejected(_) | CliError::Untrusted(_) | CliError::PolicyViolated(_) => {
+                ExitCode::from(1)
+            }
+            CliError::Input(_) => ExitCode::from(3),
+            CliError::Prover(_) => ExitCode::from(4),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let (kind, message) = match self {
+            CliError::Rejected(message) => ("rejected", message),
+            CliError::Input(message) => ("input", message),
+            CliError::Prover(message) => ("prover", message),
+            CliError::Untrusted(verdict) => {
+                return json!({
+                    "error": "rejected",
+                    "message": verdict.to_string(),
+                    "verdict": verdict,
+                })
+            }
+            CliError::PolicyViolated(report) => {
+                return json!({
+                    "error": "rejected",
+                    "message": "The claims do not satisfy the policy",
+                    "policy": report,
+                })
+            }
+        };
+        json!({ "error": kind, "message": message })
+    }
+}
+
+impl From<ProveError> for CliError {
+    fn from(err: ProveError) -> Self {
+        match err {
+            ProveError::ProverFailure(_) => CliError::Prover(err.to_string()),
+            _ => CliError::Rejected(err.to_string()),
+        }
+    }
+}
+
+pub fn run(command: Command) -> Result<Report, CliError> {
+    match command {
+        Command::Claims(args) => claims(args),
+        Command::Sign(SignCommand::Claims(args)) => sign_claims(args),
+        Command::Sign(SignCommand::Licence(args)) => sign_licence(args),
+        Command::Sign(SignCommand::Passport(args)) => sign_passport(args),
+        Command::Sign(SignCommand::RecallList(args)) => sign_recall_list(args),
+        Command::Sign(SignCommand::LicenceStatus(args)) => sign_licence_status(args),
+        Command::Sign(SignCommand::ConflictZones(args)) => sign_conflict_zones(args),
+        Command::Sign(SignCommand::CustodyTransfer(args)) => sign_custody_transfer(args),
+        Command::Prove(args) => prove(args),
+        Command::Verify(args) => verify(args),
+        Command::Fingerprint(args) => fingerprint(args),
+        Command::Keygen(args) => keygen(args),
+        Command::Inspect(args) => inspect(args),
+        Command::Tui => unreachable!("the TUI is run directly from main"),
+    }
+}
+
+fn read_file(path: &str) -> Result<String, CliError> {
+    std::fs::read_to_string(path)
+        .map_err(|err| CliError::Input(format!("Could not read {path}: {err}")))
+}
+
+fn write_file(path: &str, contents: &[u8]) -> Result<(), CliError> {
+    std::fs::write(path, contents)
+        .map_err(|err| CliError::Input(format!("Could not write {path}: {err}")))
+}
+
+fn fingerprint_of(pk: &str) -> Result<String, CliError> {
+    compute_fingerprint(pk)
+        .map_err(|err| CliError::Input(format!("Could not fingerprint key: {err}")))
+}
+
+fn claims_json(claims: &CustomClaims) -> Value {
+    claims
+        .claims
+        .iter()
+        .map(|claim| (claim.key.clone(), Value::String(claim.value.clone())))
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+fn split_claim(claim: &str) -> Result<(&str, &str), CliError> {
+    claim
+        .split_once('=')
+        .ok_or_else(|| CliError::Input(format!("Claim '{claim}' is not of the form key=value")))
+}
+
+fn claims(args: ClaimsArgs) -> Result<Report, CliError> {
+    let mut claims = CustomClaims::new();
+    for claim in args.claims.iter() {
+        let (key, value) = split_claim(claim)?;
+        claims.add(key.to_string(), value.to_string());
+    }
+    for claim in args.file_claims.iter() {
+        let (key, path) = split_claim(claim)?;
+        claims.add(key.to_string(), read_file(path)?);
+    }
+
+    let claims_string = serde_json::to_string_pretty(&claims).unwrap();
+    write_file(&args.output, claims_string.as_bytes())?;
+
+    Ok(Report {
+        text: format!("Claims written to {}", args.output),
+        json: json!({ "path": args.output, "claims": claims_json(&claims) }),
+    })
+}
+
+fn issuer(signing: &SigningArgs) -> Result<Issuer, CliError> {
+    read_file(&signing.signing_key)?
+        .parse::<Issuer>()
+        .map_err(|err| CliError::Input(format!("Could not parse signing key: {err}")))
+}
+
+/// Signs `claims` with the key in `signing` and writes the token out.
+fn sign(
+    signing: &SigningArgs,
+    claims: &CustomClaims,
+    registered: &RegisteredClaims,
+) -> Result<Report, CliError> {
+    let issuer = issuer(signing)?;
+    let token = issuer
+        .generate_token_with_registered_claims(claims, registered)
+        .map_err(|err| CliError::Input(format!("Could not sign token: {err}")))?;
+    write_file(&signing.output, token.as_bytes())?;
+
+    Ok(Report {
+        text: format!(
+            "Token signed with {} written to {}",
+            issuer.algorithm().name(),
+            signing.output
+        ),
+        json: json!({
+            "path": signing.output,
+            "algorithm": issuer.algorithm().name(),
+            "claims": claims_json(claims),
+        }),
+    })
+}
+
+fn sign_claims(args: SignClaimsArgs) -> Result<Report, CliError> {
+    let claims: CustomClaims = serde_json::from_str(&read_file(&args.claims)?)
+        .map_err(|err| CliError::Input(format!("Could not parse claims file: {err}")))?;
+    let registered = RegisteredClaims {
+        expiration: args.expires_at,
+        not_before: args.not_before,
+        issued_at: Some(Utc::now()),
+        issuer: args.issuer,
+        subject: args.subject,
+        jwt_id: args.jwt_id,
+    };
+    sign(&args.signing, &claims, &registered)
+}
+
+fn sign_licence(args: SignLicenceArgs) -> Result<Report, CliError> {
+    let licence = MiningLicence {
+        issuer_id: args.issuer_id,
+        subject_id: args.subject_id,
+        subject_pk: read_file(&args.subject_pk)?,
+        issue_date: args.valid_from,
+        expiry_date: args.valid_to,
+        country_of_operation: args.country,
+        region_of_operation: args.region,
+        sites: args.sites,
+        status_index: args.status_index,
+    }
+    .normalised()
+    .map_err(|err| CliError::Input(err.to_string()))?;
+    let registered = licence
+        .registered_claims()
+        .issued_at(Utc::now())
+        .jwt_id(Uuid::new_v4().to_string());
+    sign(&args.signing, &(&licence).into(), &registered)
+}
+
+fn sign_passport(args: SignPassportArgs) -> Result<Report, CliError> {
+    let passport = ProductPassport {
+        shipment_id: args
+            .shipment_id
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
+        product: args.product,
+        issue_date: args.issue_date,
+        sources: args
+            .sources
+            .iter()
+            .map(|(path, mass_kg)| {
+                Ok(SourceShare {
+                    licence_digest: token_digest(&read_file(path)?),
+                    mass_kg: *mass_kg,
+                })
+            })
+            .collect::<Result<_, CliError>>()?,
+        declared_mass_kg: args.declared_mass_kg,
+        attributes: args
+            .claims
+            .iter()
+            .map(|claim| {
+                let (key, value) = split_claim(claim)?;
+                Ok((key.to_string(), value.to_string()))
+            })
+            .collect::<Result<BTreeMap<_, _>, CliError>>()?,
+    };
+    if let Some(key) = PASSPORT_CLAIMS
+        .into_iter()
+        .find(|key| passport.attributes.contains_key(*key))
+    {
+        return Err(CliError::Input(format!(
+            "Claim '{key}' must be given by its own option"
+        )));
+    }
+    passport
+        .validate()
+        .map_err(|err| CliError::Input(err.to_string()))?;
+    sign(
+        &args.signing,
+        &(&passport).into(),
+        &passport.registered_claims(),
+    )
+}
+
+fn sign_recall_list(args: SignRecallListArgs) -> Result<Report, CliError> {
+    let list = RecallList {
+        issuer_id: args.issuer_id,
+        publication_date: args.publication_date,
+        shipment_ids: args.shipment_ids,
+    };
+    sign(&args.signing, &(&list).into(), &list.registered_claims())
+}
+
+fn sign_licence_status(args: SignLicenceStatusArgs) -> Result<Report, CliError> {
+    let mut list = LicenceStatusList::new(args.issuer_id, args.publication_date, args.size);
+    for index in args.revoked {
+        list.revoke(index)
+            .map_err(|err| CliError::Input(err.to_string()))?;
+    }
+    sign(&args.signing, &(&list).into(), &list.registered_claims())
+}
+
+fn sign_conflict_zones(args: SignConflictZonesArgs) -> Result<Report, CliError> {
+    let conflict_zone_list = ConflictZoneList {
+        publisher_id: args.publisher_id,
+        publication_date: args.publication_date,
+        conflict_zones: serde_json::from_str(&read_file(&args.conflict_zones)?)
+            .map_err(|err| CliError::Input(format!("Could not parse conflict zones: {err}")))?,
+    }
+    .normalised()
+    .map_err(|err| CliError::Input(err.to_string()))?;
+    if args.root {
+        let root = ConflictZoneRoot::from_list(&conflict_zone_list)
+            .map_err(|err| CliError::Input(err.to_string()))?;
+        return sign(&args.signing, &(&root).into(), &root.registered_claims());
+    }
+    sign(
+        &args.signing,
+        &(&conflict_zone_list).into(),
+        &conflict_zone_list.registered_claims(),
+    )
+}
+
+fn sign_custody_transfer(args: SignCustodyTransferArgs) -> Result<Report, CliError> {
+    let transfer = CustodyTransfer {
+        shipment_id: args.shipment_id,
+        issuer_id: args.issuer_id,
+        subject_id: args.subject_id,
+        subject_pk: read_file(&args.subject_pk)?,
+        transfer_date: args.transfer_date,
+    };
+    sign(
+        &args.signing,
+        &(&transfer).into(),
+        &transfer.registered_claims(),
+    )
+}
+
+fn prove(args: ProveArgs) -> Result<Report, CliError> {
+    let custody_chain = args
+        .custody_tokens
+        .iter()
+        .map(|path| read_file(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let conflict_zones = match &args.conflict_zone_tree {
+        Some(path) => ConflictZoneInput::Tree {
+            root: read_file(&args.conflict_zones)?,
+            zones: serde_json::from_str(&read_file(path)?)
+                .map_err(|err| CliError::Input(format!("Could not parse conflict zones: {err}")))?,
+        },
+        None => ConflictZoneInput::List(read_file(&args.conflict_zones)?),
+    };
+
+    let disclosure = (!args.revealed_claims.is_empty()
+        || !args.committed_claims.is_empty()
+        || !args.hidden_claims.is_empty())
+    .then(|| DisclosureSet {
+        reveal: args.revealed_claims.clone(),
+        commit: args.committed_claims.clone(),
+        hide: args.hidden_claims.clone(),
+    });
+
+    let passport = read_file(&args.passport)?;
+    let proof = prove_token_validation(
+        passport.clone(),
+        args.licences
+            .iter()
+            .map(|path| read_file(path))
+            .collect::<Result<Vec<_>, _>>()?,
+        read_file(&args.mining_authority_pk)?,
+        conflict_zones,
+        read_file(&args.conflict_zone_authority_pk)?,
+        custody_chain,
+        &ProofOptions::default()
+            .commit_full_keys(args.commit_full_keys)
+            .strict(args.strict)
+            .licence_status_list(
+                args.licence_status_list
+                    .as_deref()
+                    .map(read_file)
+                    .transpose()?,
+            )
+            .recall_list(args.recall_list.as_deref().map(read_file).transpose()?)
+            .nullifier_context(args.nullifier_context)
+            .disclosure(disclosure)
+            .predicates(args.predicates),
+    )?;
+
+    // The salts let the prover open a commitment to whoever needs to see the claim
+    let openings = if args.committed_claims.is_empty() {
+        Map::new()
+    } else {
+        let passport_claims = passport_claims(&passport)?;
+        args.committed_claims
+            .iter()
+            .map(|key| {
+                let opening = json!({
+                    "salt": disclosure_salt(&proof.disclosure_seed, key),
+                    "value": passport_claims.get(key),
+                });
+                (key.clone(), opening)
+            })
+            .collect()
+    };
+
+    let mut serialized_receipt = Vec::new();
+    proof
+        .receipt
+        .serialize(&mut serialized_receipt)
+        .expect("Could not serialise the receipt");
+    write_file(&args.receipt, &serialized_receipt)?;
+
+    let mut text = format!(
+        "Proof written to {} using image ID {}\n\nThe following information was proved about the product passport:\n{}",
+        args.receipt,
+        proof.image_id,
+        proof.public_output.claims.pretty_print()
+    );
+    for (key, opening) in &openings {
+        text += &format!(
+            "\n\nThe commitment to '{key}' opens with --opening {key}={}:{}",
+            opening["salt"].as_str().unwrap_or_default(),
+            opening["value"].as_str().unwrap_or_default()
+        );
+    }
+
+    Ok(Report {
+        text,
+        json: json!({
+            "path": args.receipt,
+            "image_id": proof.image_id.to_string(),
+            "claims": claims_json(&proof.public_output.claims),
+            "openings": openings,
+        }),
+    })
+}
+
+/// The claims of a passport token as the guest sees them, without checking
+/// its signature, which the proof has already done.
+fn passport_claims(passport: &str) -> Result<CustomClaims, CliError> {
+    let parse_error = |err: &dyn std::fmt::Display| {
+        CliError::Input(format!("Could not parse passport claims: {err}"))
+    };
+    let claims = UntrustedToken::new(passport.trim())
+        .map_err(|err| parse_error(&err))?
+        .deserialize_claims_unchecked::<TokenClaims>()
+        .map_err(|err| parse_error(&err))?;
+    let custom: &CustomClaims = &claims.custom;
+    let passport = ProductPassport::try_from(custom)
+        .map_err(|err| CliError::Input(format!("Could not parse passport: {err}")))?;
+    Ok((&passport).into())
+}
+
+fn read_receipt(path: &str) -> Result<(Receipt, PublicOutput), CliError> {
+    let bytes = std::fs::read(path)
+        .map_err(|err| CliError::Input(format!("Could not read {path}: {err}")))?;
+    let receipt = Receipt::try_from_slice(&bytes)
+        .map_err(|err| CliError::Input(format!("Could not deserialise receipt: {err}")))?;
+    let public_output: PublicOutput = receipt
+        .journal
+        .decode()
+        .map_err(|err| CliError::Input(format!("Could not decode receipt journal: {err}")))?;
+    Ok((receipt, public_output))
+}
+
+fn verify(args: VerifyArgs) -> Result<Report, CliError> {
+    let (receipt, public_output) = read_receipt(&args.receipt)?;
+    receipt
+        .verify(VERIFY_TOKEN_WITH_SOME_KEY_ID)
+        .map_err(|err| CliError::Rejected(format!("Receipt verification failed: {err}")))?;
+
+    let [mining_authority, conflict_zone_authority] = match public_output.fingerprints.as_slice() {
+        [mining_authority, conflict_zone_authority] => [mining_authority, conflict_zone_authority],
+        _ => {
+            return Err(CliError::Rejected(
+                "The receipt does not commit the authority key fingerprints".to_string(),
+            ))
+        }
+    };
+    let mut text = format!(
+        "Verification succeeded!\nThe prover has proved they hold a product passport that is authenticated by a mining licence that is authenticated by the following national mining authority key:\n{mining_authority}\n\nThe conflict zone list was signed by the following conflict zone authority key:\n{conflict_zone_authority}\n\nThe following information was proved about the product passport:\n{}",
+        public_output.claims.pretty_print()
+    );
+
+    if let Some(path) = &args.conflict_zones {
+        let matched = conflict_zones_match(&public_output, read_file(path)?).map_err(|err| {
+            CliError::Input(format!("Could not parse conflict zones {path}: {err}"))
+        })?;
+        if !matched {
+            return Err(CliError::Rejected(format!(
+                "The proof was not generated against the conflict zones in {path}"
+            )));
+        }
+        text += &format!("\n\nThe proof was generated against the conflict zones in {path}");
+    }
+
+    if !args.trusted_mining_authorities.is_empty() {
+        if !mining_authority_trusted(&public_output, &args.trusted_mining_authorities) {
+            return Err(CliError::Rejected(format!(
+                "The mining licence was signed by the untrusted key {mining_authority}"
+            )));
+        }
+        text += "\n\nThe mining licence was signed by a trusted national mining authority";
+    }
+
+    if let Some(path) = &args.trusted_conflict_zone_authority_pk {
+        if !conflict_zone_authority_matches(&public_output, read_file(path)?) {
+            return Err(CliError::Rejected(format!(
+                "The conflict zone list was not signed by the key in {path}"
+            )));
+        }
+        text += &format!("\n\nThe conflict zone list was signed by the key in {path}");
+    }
+
+    let verdict = match &args.registry {
+        Some(path) => {
+            let registry = read_file(path)?.parse::<TrustRegistry>().map_err(|err| {
+                CliError::Input(format!("Could not parse trust registry {path}: {err}"))
+            })?;
+            let verdict = registry.check(&public_output);
+            if !verdict.trusted {
+                return Err(CliError::Untrusted(verdict));
+            }
+            for check in &verdict.authorities {
+                text += &format!(
+                    "\n\nThe {} key belongs to {}, which is in the trust registry {path}",
+                    check.role,
+                    check.name.as_deref().unwrap_or_default()
+                );
+            }
+            Some(verdict)
+        }
+        None => None,
+    };
+
+    let policy_report = match &args.policy {
+        Some(path) => {
+            let contents = read_file(path)?;
+            let policy = if path.ends_with(".json") {
+                Policy::from_json(&contents)
+            } else {
+                Policy::from_toml(&contents)
+            }
+            .map_err(|err| CliError::Input(format!("{path}: {err}")))?;
+            let report = policy.evaluate(&public_output.claims, Utc::now());
+            if !report.passed {
+                return Err(CliError::PolicyViolated(report));
+            }
+            text += &format!("\n\nThe claims satisfy the policy in {path}:\n{report}");
+            Some(report)
+        }
+        None => None,
+    };
+
+    if let Some(min_version) = args.min_conflict_zones_version {
+        if !conflict_zones_version_at_least(&public_output, min_version) {
+            return Err(CliError::Rejected(format!(
+                "The conflict zone list is older than version {min_version}"
+            )));
+        }
+        text += &format!("\n\nThe conflict zone list is at least version {min_version}");
+    }
+
+    if let Some(max_age_days) = args.max_licence_status_age_days {
+        if !licence_status_fresh(&public_output, Duration::days(max_age_days), Utc::now()) {
+            return Err(CliError::Rejected(format!(
+                "The licences were not checked against a status list from the last {max_age_days} days"
+            )));
+        }
+        text += &format!(
+            "\n\nThe licences were not revoked according to a status list from the last {max_age_days} days"
+        );
+    }
+
+    if let Some(max_age_days) = args.max_recall_list_age_days {
+        if !recall_list_fresh(&public_output, Duration::days(max_age_days), Utc::now()) {
+            return Err(CliError::Rejected(format!(
+                "The passport was not checked against a recall list from the last {max_age_days} days"
+            )));
+        }
+        text += &format!(
+            "\n\nThe passport was not recalled according to a recall list from the last {max_age_days} days"
+        );
+    }
+
+    for opening in &args.openings {
+        let (key, salt, value) = opening
+            .split_once('=')
+            .and_then(|(key, rest)| {
+                let (salt, value) = rest.split_once(':')?;
+                Some((key, salt, value))
+            })
+            .ok_or_else(|| {
+                CliError::Input(format!(
+                    "Opening '{opening}' is not of the form KEY=SALT:VALUE"
+                ))
+            })?;
+        if !commitment_opens(&public_output, key, value, salt) {
+            return Err(CliError::Rejected(format!(
+                "The commitment to '{key}' does not open to '{value}'"
+            )));
+        }
+        text += &format!("\n\nThe committed claim '{key}' is '{value}'");
+    }
+
+    for predicate in &args.required_predicates {
+        match predicate_holds(&public_output, predicate) {
+            Some(true) => text += &format!("\n\nThe passport satisfies {predicate}"),
+            Some(false) => {
+                return Err(CliError::Rejected(format!(
+                    "The passport does not satisfy {predicate}"
+                )))
+            }
+            None => {
+                return Err(CliError::Rejected(format!(
+                    "The receipt does not prove whether the passport satisfies {predicate}"
+                )))
+            }
+        }
+    }
+
+    // Recorded last, so a receipt rejected for any other reason is not used up
+    let nullifier = match (&args.nullifier_store, &args.nullifier_context) {
+        (Some(path), Some(context)) => {
+            let mut store = if std::path::Path::new(path).exists() {
+                read_file(path)?.parse::<NullifierStore>().map_err(|err| {
+                    CliError::Input(format!("Could not parse nullifier store {path}: {err}"))
+                })?
+            } else {
+                NullifierStore::default()
+            };
+            let nullifier = store
+                .record(&public_output, context)
+                .map_err(|err| CliError::Rejected(err.to_string()))?;
+            let store_json = serde_json::to_string_pretty(&store).unwrap();
+            write_file(path, store_json.as_bytes())?;
+            text += &format!(
+                "\n\nThe passport has not been used before in context '{context}'; its nullifier was recorded in {path}"
+            );
+            Some(nullifier)
+        }
+        _ => None,
+    };
+
+    Ok(Report {
+        text,
+        json: json!({
+            "verified": true,
+            "mining_authority_fingerprint": mining_authority,
+            "conflict_zone_authority_fingerprint": conflict_zone_authority,
+            "verdict": verdict,
+            "policy": policy_report,
+            "nullifier": nullifier,
+            "claims": claims_json(&public_output.claims),
+        }),
+    })
+}
+
+fn fingerprint(args: FingerprintArgs) -> Result<Report, CliError> {
+    let fingerprint = fingerprint_of(&read_file(&args.public_key)?)?;
+    Ok(Report {
+        text: fingerprint.clone(),
+        json: json!({ "fingerprint": fingerprint }),
+    })
+}
+
+fn keygen(args: KeygenArgs) -> Result<Report, CliError> {
+    let pair = generate_key_pair(args.algorithm).map_err(|err| CliError::Input(err.to_string()))?;
+    let secret_key_path = args.output_dir.join(format!("{}_sk.jwk", args.name));
+    let public_key_path = args.output_dir.join(format!("{}_pk.jwk", args.name));
+    pair.write(&secret_key_path, &public_key_path)
+        .map_err(|err| CliError::Input(format!("Could not write key pair: {err}")))?;
+
+    Ok(Report {
+        text: format!(
+            "{} secret key written to {}\nPublic key written to {}\nFingerprint: {}",
+            args.algorithm.name(),
+            secret_key_path.display(),
+            public_key_path.display(),
+            pair.kid
+        ),
+        json: json!({
+            "algorithm": args.algorithm.name(),
+            "secret_key_path": secret_key_path,
+            "public_key_path": public_key_path,
+            "fingerprint": pair.kid,
+        }),
+    })
+}
+
+fn inspect(args: InspectArgs) -> Result<Report, CliError> {
+    let bytes = std::fs::read(&args.path)
+        .map_err(|err| CliError::Input(format!("Could not read {}: {err}", args.path)))?;
+
+    // Tokens are three base64 segments separated by dots, anything else is treated as a receipt
+    let token = std::str::from_utf8(&bytes)
+        .ok()
+        .map(str::trim)
+        .filter(|token| token.split('.').count() == 3);
+
+    match token {
+        Some(token) => inspect_token(token),
+        None => {
+            let (_, public_output) = read_receipt(&args.path)?;
+            Ok(Report {
+                text: format!(
+                    "Receipt (not verified) committing the key fingerprints {} and the claims:\n{}",
+                    public_output.fingerprints.join(", "),
+                    public_output.claims.pretty_print()
+                ),
+                json: json!({
+                    "kind": "receipt",
+                    "verified": false,
+                    "fingerprints": public_output.fingerprints,
+                    "pks": public_output.pks,
+                    "claims": claims_json(&public_output.claims),
+                }),
+            })
+        }
+    }
+}
+
+fn inspect_token(token: &str) -> Result<Report, CliError> {
+    let untrusted = UntrustedToken::new(token)
+        .map_err(|err| CliError::Input(format!("Could not parse token: {err}")))?;
+    let claims = untrusted
+        .deserialize_claims_unchecked::<TokenClaims>()
+        .map_err(|err| CliError::Input(format!("Could not parse token claims: {err}")))?;
+
+    let date = |date: Option<DateTime<Utc>>| date.map(|date| jwt_core::format_date(&date));
+    let registered = json!({
+        "exp": date(claims.expiration),
+        "nbf": date(claims.not_before),
+        "iat": date(claims.issued_at),
+        "iss": claims.custom.iss,
+        "sub": claims.custom.sub,
+        "jti": claims.custom.jti,
+    });
+
+    Ok(Report {
+        text: format!(
+            "Token (signature not verified) signed with {}\nRegistered claims: {:#}\nClaims: {}",
+            untrusted.algorithm(),
+            registered,
+            claims.custom.pretty_print()
+        ),
+        json: json!({
+            "kind": "token",
+            "verified": false,
+            "algorithm": untrusted.algorithm(),
+            "registered_claims": registered,
+            "claims": claims_json(&claims.custom),
+        }),
+    })
+}
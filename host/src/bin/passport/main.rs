@@ -0,0 +1,87 @@
+mod commands;
+mod tui;
+
+use std::process::ExitCode;
+
+use clap::Parser;
+use commands::Command;
+use tracing_subscriber::EnvFilter;
+
+/// Sign, prove and verify zero-knowledge product passports
+#[derive(Parser, Debug)]
+#[command(name = "passport", version, about, long_about = None)]
+struct Cli {
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true, conflicts_with = "quiet")]
+    json: bool,
+
+    /// Only print errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Log filter, e.g. `info` or `risc0_zkvm=debug` (defaults to `RUST_LOG`)
+    #[arg(long, global = true)]
+    log_level: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let filter = match &cli.log_level {
+        Some(level) => match EnvFilter::try_new(level) {
+            Ok(filter) => filter,
+            Err(err) => {
+                eprintln!("Invalid log level '{level}': {err}");
+                return ExitCode::from(2);
+            }
+        },
+        None => EnvFilter::from_default_env(),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+
+    if let Command::Tui = cli.command {
+        return match tui::run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    match commands::run(cli.command) {
+        Ok(report) => {
+            if cli.json {
+                println!("{:#}", report.json);
+            } else if !cli.quiet {
+                println!("{}", report.text);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            if cli.json {
+                println!("{:#}", err.to_json());
+            }
+            eprintln!("{err}");
+            err.exit_code()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+}
@@ -43,11 +43,15 @@
 
 use borsh::de::BorshDeserialize;
 use borsh::BorshSerialize;
+use chrono::Utc;
 use color_eyre::Result;
 use crossterm::event::{self, KeyCode, KeyEvent};
-use host::{compute_fingerprint, prove_token_validation};
-use jwt_core::PublicOutput;
-use jwt_core::{CustomClaims, Issuer};
+use host::{
+    conflict_zone_authority_matches, conflict_zones_match, conflict_zones_version_at_least,
+    prove_token_validation,
+};
+use jwt_core::{ConflictZoneList, Issuer, MiningLicence, ProductPassport};
+use jwt_core::{ProofOptions, PublicOutput};
 use methods::VERIFY_TOKEN_WITH_SOME_KEY_ID;
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Constraint, Flex, Layout, Margin, Offset, Rect};
@@ -60,7 +64,8 @@ use std::fs::File;
 use std::io::prelude::*;
 use uuid::Uuid;
 
-fn main() -> Result<()> {
+/// Runs the interactive terminal interface until the user quits.
+pub fn run() -> Result<()> {
     color_eyre::install()?;
 
     match ratatui::run(|terminal| App::default().run(terminal)) {
@@ -76,6 +81,7 @@ pub struct App {
     home: SelectScreen,
     sign_licence_form: InputForm,
     sign_product_passport_form: InputForm,
+    sign_conflict_zones_form: InputForm,
     prove_form: InputForm,
     verify_form: InputForm,
     result_text: String,
@@ -99,7 +105,11 @@ impl Default for App {
                 StringField::new("Valid from", "2025-01-01T00:00:00Z".to_owned()),
                 StringField::new("Valid to", "2035-01-01T00:00:00Z".to_owned()),
                 StringField::new("Country of operation", "GB".to_owned()),
-                StringField::new("Region of operation", "Cornwall".to_owned()),
+                StringField::new("Region of operation", "GB-CON".to_owned()),
+                StringField::new(
+                    "Site coordinates (LON,LAT; separated by semicolons)",
+                    "-5.0527,50.266".to_owned(),
+                ),
                 StringField::new("Path to output licence file", "./licence.jwt".to_owned()),
             ]),
             focus: 0,
@@ -121,6 +131,26 @@ impl Default for App {
             focus: 0,
         };
 
+        let sign_conflict_zones_form = InputForm {
+            fields: Vec::from([
+                StringField::new("Publisher ID", "Conflict_Zone_Authority".to_owned()),
+                StringField::new(
+                    "Path to publisher signing key file",
+                    "./test_data/conflict_zone_authority_sk.jwk".to_owned(),
+                ),
+                StringField::new(
+                    "Path to conflict zones file",
+                    "./test_data/conflict_zones.json".to_owned(),
+                ),
+                StringField::new("Publication date", "2025-06-01T00:00:00Z".to_owned()),
+                StringField::new(
+                    "Path to output conflict zone list",
+                    "./conflict_zones.jwt".to_owned(),
+                ),
+            ]),
+            focus: 0,
+        };
+
         let prove_form = InputForm {
             fields: Vec::from([
                 StringField::new(
@@ -133,19 +163,35 @@ impl Default for App {
                     "./test_data/national_mining_authority_pk.jwk".to_owned(),
                 ),
                 StringField::new(
-                    "Path to conflict zones file",
-                    "./test_data/conflict_zones.json".to_owned(),
+                    "Path to signed conflict zone list",
+                    "./conflict_zones.jwt".to_owned(),
+                ),
+                StringField::new(
+                    "Path to conflict zone authority verification key",
+                    "./test_data/conflict_zone_authority_pk.jwk".to_owned(),
                 ),
                 StringField::new("Path to output proof", "./receipt.bin".to_owned()),
+                StringField::new(
+                    "Paths to custody transfer tokens (comma-separated)",
+                    "".to_owned(),
+                ),
             ]),
             focus: 0,
         };
 
         let verify_form = InputForm {
-            fields: Vec::from([StringField::new(
-                "Path to proof",
-                "./receipt.bin".to_owned(),
-            )]),
+            fields: Vec::from([
+                StringField::new("Path to proof", "./receipt.bin".to_owned()),
+                StringField::new(
+                    "Path to conflict zones file",
+                    "./test_data/conflict_zones.json".to_owned(),
+                ),
+                StringField::new(
+                    "Path to trusted conflict zone authority key",
+                    "./test_data/conflict_zone_authority_pk.jwk".to_owned(),
+                ),
+                StringField::new("Minimum conflict zone list version", "1".to_owned()),
+            ]),
             focus: 0,
         };
 
@@ -155,6 +201,7 @@ impl Default for App {
             home: SelectScreen::default(),
             sign_licence_form,
             sign_product_passport_form,
+            sign_conflict_zones_form,
             prove_form,
             verify_form,
             result_text: "".to_string(),
@@ -175,6 +222,7 @@ enum AppWindow {
     Home,
     SignLicence,
     SignPP,
+    SignConflictZones,
     Prove,
     Verify,
     Result,
@@ -196,25 +244,34 @@ impl App {
                 AppWindow::SignLicence => {
                     let args = self.sign_licence_form.get_form_fields();
 
-                    let mut claims = CustomClaims::new();
-
-                    claims.add("issuer_id".to_string(), args[0].clone());
-                    // 1 is signing key
-                    claims.add("subject_id".to_string(), args[2].clone());
                     let mut f = std::fs::File::open(&args[3])
-                        .expect(&format!("Could not find file {}", args[3]));
+                        .unwrap_or_else(|_| panic!("Could not find file {}", args[3]));
                     let mut subject_pk = String::new();
                     let _ = f
                         .read_to_string(&mut subject_pk)
                         .expect("Could not read from file");
-                    claims.add("subject_pk".to_string(), subject_pk);
-                    claims.add("issue_date".to_string(), args[4].clone());
-                    claims.add("expiry_date".to_string(), args[5].clone());
-                    claims.add("country_of_operation".to_string(), args[6].clone());
-                    claims.add("region_of_operation".to_string(), args[7].clone());
+
+                    let licence = MiningLicence {
+                        issuer_id: args[0].clone(),
+                        // 1 is signing key
+                        subject_id: args[2].clone(),
+                        subject_pk,
+                        issue_date: args[4].parse().expect("Could not parse valid from date"),
+                        expiry_date: args[5].parse().expect("Could not parse valid to date"),
+                        country_of_operation: args[6].clone(),
+                        region_of_operation: args[7].clone(),
+                        sites: args[8]
+                            .split(';')
+                            .filter(|site| !site.trim().is_empty())
+                            .map(|site| site.parse().expect("Could not parse site coordinates"))
+                            .collect(),
+                        status_index: None,
+                    }
+                    .normalised()
+                    .expect("Unknown country or region of operation");
 
                     let mut f = std::fs::File::open(&args[1])
-                        .expect(&format!("Could not find file {}", args[1]));
+                        .unwrap_or_else(|_| panic!("Could not find file {}", args[1]));
                     let mut secret_key = String::new();
                     let _ = f
                         .read_to_string(&mut secret_key)
@@ -224,12 +281,17 @@ impl App {
                         .parse::<Issuer>()
                         .expect("failed to create issuer from secret key");
 
+                    let registered = licence
+                        .registered_claims()
+                        .issued_at(Utc::now())
+                        .jwt_id(Uuid::new_v4().to_string());
+
                     let token = iss
-                        .generate_token(&claims)
+                        .generate_token_with_registered_claims(&(&licence).into(), &registered)
                         .expect("failed to generate token");
 
-                    let mut f = File::create(&args[8]).expect("Could not create JWT file");
-                    f.write_all(&token.as_bytes())
+                    let mut f = File::create(&args[9]).expect("Could not create JWT file");
+                    f.write_all(token.as_bytes())
                         .expect("Could not write to file");
 
                     self.window = AppWindow::Home;
@@ -239,14 +301,17 @@ impl App {
                 AppWindow::SignPP => {
                     let args = self.sign_product_passport_form.get_form_fields();
 
-                    let mut claims = CustomClaims::new();
-
-                    claims.add("shipment_id".to_string(), Uuid::new_v4().to_string());
-                    claims.add("product".to_string(), args[0].clone());
-                    claims.add("issue_date".to_string(), args[1].clone());
+                    let passport = ProductPassport {
+                        shipment_id: Uuid::new_v4().to_string(),
+                        product: args[0].clone(),
+                        issue_date: args[1].parse().expect("Could not parse issue date"),
+                        sources: Vec::new(),
+                        declared_mass_kg: None,
+                        attributes: Default::default(),
+                    };
 
                     let mut f = std::fs::File::open(&args[2])
-                        .expect(&format!("Could not find file {}", args[2]));
+                        .unwrap_or_else(|_| panic!("Could not find file {}", args[2]));
                     let mut secret_key = String::new();
                     let _ = f
                         .read_to_string(&mut secret_key)
@@ -255,12 +320,50 @@ impl App {
                     let iss = secret_key
                         .parse::<Issuer>()
                         .expect("failed to create issuer from secret key");
-                    let token = iss
-                        .generate_token(&claims)
-                        .expect("failed to generate token");
+                    let token = passport.sign(&iss).expect("failed to generate token");
 
                     let mut f = File::create(&args[3]).expect("Could not create JWT file");
-                    f.write_all(&token.as_bytes())
+                    f.write_all(token.as_bytes())
+                        .expect("Could not write to file");
+
+                    self.window = AppWindow::Home;
+                    self.state = AppState::Running;
+                }
+                AppWindow::SignConflictZones => {
+                    let args = self.sign_conflict_zones_form.get_form_fields();
+
+                    let mut f = File::open(&args[2]).expect("Could not find conflict zones file");
+                    let mut conflict_zones = String::new();
+                    f.read_to_string(&mut conflict_zones)
+                        .expect("Could not parse conflict zones from file");
+
+                    let conflict_zone_list = ConflictZoneList {
+                        publisher_id: args[0].clone(),
+                        publication_date: args[3]
+                            .parse()
+                            .expect("Could not parse publication date"),
+                        conflict_zones: serde_json::from_str(&conflict_zones)
+                            .expect("Could not parse conflict zones"),
+                    }
+                    .normalised()
+                    .expect("Unknown country or region in conflict zones");
+
+                    let mut f = std::fs::File::open(&args[1])
+                        .unwrap_or_else(|_| panic!("Could not find file {}", args[1]));
+                    let mut secret_key = String::new();
+                    let _ = f
+                        .read_to_string(&mut secret_key)
+                        .expect("Could not read from file");
+
+                    let iss = secret_key
+                        .parse::<Issuer>()
+                        .expect("failed to create issuer from secret key");
+                    let token = conflict_zone_list
+                        .sign(&iss)
+                        .expect("failed to generate token");
+
+                    let mut f = File::create(&args[4]).expect("Could not create JWT file");
+                    f.write_all(token.as_bytes())
                         .expect("Could not write to file");
 
                     self.window = AppWindow::Home;
@@ -289,19 +392,61 @@ impl App {
                     f.read_to_string(&mut conflict_zones)
                         .expect("Could not parse conflict zones from file");
 
-                    let (receipt, _journal) =
-                        prove_token_validation(passport, licence, pk, conflict_zones);
-
-                    let mut f =
-                        std::fs::File::create(&args[4]).expect("Could not create receipt file");
-                    let mut serialized_receipt = Vec::new();
-                    receipt
-                        .serialize(&mut serialized_receipt)
-                        .expect("Could not serialise the receipt");
-                    f.write_all(&serialized_receipt)
-                        .expect("Could not write receipt to file");
-
-                    self.window = AppWindow::Home;
+                    let mut f = File::open(&args[4])
+                        .expect("Could not find conflict zone authority key file");
+                    let mut conflict_zones_pk = String::new();
+                    f.read_to_string(&mut conflict_zones_pk)
+                        .expect("Could not parse conflict zone authority key from file");
+
+                    let custody_chain: Vec<String> = args[6]
+                        .split(',')
+                        .map(|path| path.trim())
+                        .filter(|path| !path.is_empty())
+                        .map(|path| {
+                            let mut f = File::open(path).unwrap_or_else(|_| {
+                                panic!("Could not find custody token file {}", path)
+                            });
+                            let mut custody_token = String::new();
+                            f.read_to_string(&mut custody_token)
+                                .expect("Could not parse custody token from file");
+                            custody_token
+                        })
+                        .collect();
+
+                    match prove_token_validation(
+                        passport,
+                        vec![licence],
+                        pk,
+                        conflict_zones,
+                        conflict_zones_pk,
+                        custody_chain,
+                        &ProofOptions::default(),
+                    ) {
+                        Ok(proof) => {
+                            let mut f = std::fs::File::create(&args[5])
+                                .expect("Could not create receipt file");
+                            let mut serialized_receipt = Vec::new();
+                            proof
+                                .receipt
+                                .serialize(&mut serialized_receipt)
+                                .expect("Could not serialise the receipt");
+                            f.write_all(&serialized_receipt)
+                                .expect("Could not write receipt to file");
+
+                            self.result_text = format!(
+                                "Proof written to {} using image ID {}",
+                                args[5], proof.image_id
+                            );
+                            self.result_text +=
+                                "\nThe following information was proved about the product passport:";
+                            self.result_text += proof.public_output.claims.pretty_print().as_str();
+                        }
+                        Err(err) => {
+                            self.result_text = format!("Could not generate proof: {err}");
+                        }
+                    }
+                    self.show_popup = true;
+                    self.window = AppWindow::Result;
                     self.state = AppState::Running;
                 }
                 AppWindow::Verify => {
@@ -318,24 +463,72 @@ impl App {
                     let res = receipt.verify(VERIFY_TOKEN_WITH_SOME_KEY_ID);
                     if res.is_ok() {
                         self.result_text = String::from("Verification succeeded!");
-                        self.result_text += format!("\nThe prover has proved they hold a product passport that is authenticated by a mining licence that is authenticated by the following national mining authority key:").as_str();
+                        self.result_text += "\nThe prover has proved they hold a product passport that is authenticated by a mining licence that is authenticated by the following national mining authority key:".to_string().as_str();
                         let public_outputs: PublicOutput = receipt
                             .journal
                             .decode()
                             .expect("Could not decode receipt journal");
-                        let pk_digests: Vec<String> = public_outputs
-                            .pks
-                            .into_iter()
-                            .map(|pk| compute_fingerprint(pk))
-                            .collect();
-                        self.result_text += format!("{:#?}", pk_digests).as_str();
-
-                        self.result_text += format!(
+                        self.result_text +=
+                            format!("{:#?}", public_outputs.fingerprints[0]).as_str();
+                        self.result_text += "\nThe conflict zone list was signed by the following conflict zone authority key:";
+                        self.result_text +=
+                            format!("{:#?}", public_outputs.fingerprints[1]).as_str();
+
+                        self.result_text +=
                             "\nThe following information was proved about the product passport:"
-                        )
-                        .as_str();
+                                .to_string()
+                                .as_str();
                         self.result_text +=
-                            format!("{:}", public_outputs.claims.pretty_print()).as_str();
+                            public_outputs.claims.pretty_print().to_string().as_str();
+
+                        let mut f =
+                            File::open(&args[1]).expect("Could not find conflict zones file");
+                        let mut conflict_zones = String::new();
+                        f.read_to_string(&mut conflict_zones)
+                            .expect("Could not parse conflict zones from file");
+                        match conflict_zones_match(&public_outputs, conflict_zones) {
+                            Ok(true) => {
+                                self.result_text += format!(
+                                    "\nThe proof was generated against the conflict zones in {}",
+                                    args[1]
+                                )
+                                .as_str();
+                            }
+                            Ok(false) => {
+                                self.result_text = format!(
+                                    "Verification failed! The proof was not generated against the conflict zones in {}",
+                                    args[1]
+                                );
+                            }
+                            Err(err) => {
+                                self.result_text = format!(
+                                    "Verification failed! Could not parse conflict zones in {}: {err}",
+                                    args[1]
+                                );
+                            }
+                        }
+
+                        let mut f = File::open(&args[2])
+                            .expect("Could not find conflict zone authority key file");
+                        let mut trusted_pk = String::new();
+                        f.read_to_string(&mut trusted_pk)
+                            .expect("Could not parse conflict zone authority key from file");
+                        if !conflict_zone_authority_matches(&public_outputs, trusted_pk) {
+                            self.result_text = format!(
+                                "Verification failed! The conflict zone list was not signed by the key in {}",
+                                args[2]
+                            );
+                        }
+
+                        let min_version: u32 = args[3]
+                            .parse()
+                            .expect("Could not parse minimum conflict zone list version");
+                        if !conflict_zones_version_at_least(&public_outputs, min_version) {
+                            self.result_text = format!(
+                                "Verification failed! The conflict zone list is older than version {}",
+                                min_version
+                            );
+                        }
                     }
                     self.show_popup = true;
                     self.window = AppWindow::Result;
@@ -359,6 +552,7 @@ impl App {
                 AppWindow::Home => self.home.render(frame),
                 AppWindow::SignLicence => self.sign_licence_form.render(frame),
                 AppWindow::SignPP => self.sign_product_passport_form.render(frame),
+                AppWindow::SignConflictZones => self.sign_conflict_zones_form.render(frame),
                 AppWindow::Prove => self.prove_form.render(frame),
                 AppWindow::Verify => self.verify_form.render(frame),
                 AppWindow::Result => self.render_result(frame),
@@ -382,25 +576,27 @@ impl App {
                 KeyCode::Enter => match self.window {
                     AppWindow::SignLicence
                     | AppWindow::SignPP
+                    | AppWindow::SignConflictZones
                     | AppWindow::Prove
                     | AppWindow::Verify
                     | AppWindow::Result => self.state = AppState::Submitted,
-                    AppWindow::Home => match self.home.on_key_press(key) {
-                        Some(result) => {
+                    AppWindow::Home => {
+                        if let Some(result) = self.home.on_key_press(key) {
                             self.window = match result {
                                 0 => AppWindow::SignLicence,
                                 1 => AppWindow::SignPP,
-                                2 => AppWindow::Prove,
-                                3 => AppWindow::Verify,
+                                2 => AppWindow::SignConflictZones,
+                                3 => AppWindow::Prove,
+                                4 => AppWindow::Verify,
                                 _ => AppWindow::Home,
                             }
                         }
-                        _ => (),
-                    },
+                    }
                 },
                 _ => match self.window {
                     AppWindow::SignLicence => self.sign_licence_form.on_key_press(key),
                     AppWindow::SignPP => self.sign_product_passport_form.on_key_press(key),
+                    AppWindow::SignConflictZones => self.sign_conflict_zones_form.on_key_press(key),
                     AppWindow::Prove => self.prove_form.on_key_press(key),
                     AppWindow::Verify => self.verify_form.on_key_press(key),
                     AppWindow::Home => {
@@ -480,6 +676,7 @@ impl SelectScreen {
         let items = [
             "Sign a new mining licence",
             "Sign a product passport",
+            "Sign a conflict zone list",
             "Generate a Zero-Knowledge Product Passport",
             "Verify a Zero-Knowledge Product Passport",
         ];
@@ -541,8 +738,8 @@ impl InputForm {
 
         let layout = Layout::vertical(Constraint::from_lengths(vec![1; self.fields.len()]));
         let areas = area.inner(Margin::new(2, 2)).layout_vec(&layout);
-        for index in 0..self.fields.len() {
-            frame.render_widget(&self.fields[index], areas[index]);
+        for (field, field_area) in self.fields.iter().zip(areas.iter()) {
+            frame.render_widget(field, *field_area);
         }
 
         let cursor_position = areas[self.focus] + self.fields[self.focus].cursor_offset();
@@ -609,15 +806,23 @@ mod test {
         let app = App::default();
 
         let args = app.sign_licence_form.get_form_fields();
-        assert!(File::open(&format!("../{}", args[1])).is_ok());
-        assert!(File::open(&format!("../{}", args[3])).is_ok());
+        assert!(File::open(format!("../{}", args[1])).is_ok());
+        assert!(File::open(format!("../{}", args[3])).is_ok());
 
         let args = app.sign_product_passport_form.get_form_fields();
-        assert!(File::open(&format!("../{}", args[2])).is_ok());
+        assert!(File::open(format!("../{}", args[2])).is_ok());
+
+        let args = app.sign_conflict_zones_form.get_form_fields();
+        assert!(File::open(format!("../{}", args[1])).is_ok());
+        assert!(File::open(format!("../{}", args[2])).is_ok());
 
         let args = app.prove_form.get_form_fields();
-        assert!(File::open(&format!("../{}", args[2])).is_ok());
-        assert!(File::open(&format!("../{}", args[3])).is_ok());
+        assert!(File::open(format!("../{}", args[2])).is_ok());
+        assert!(File::open(format!("../{}", args[4])).is_ok());
+
+        let args = app.verify_form.get_form_fields();
+        assert!(File::open(format!("../{}", args[1])).is_ok());
+        assert!(File::open(format!("../{}", args[2])).is_ok());
     }
 
     #[test]
@@ -627,13 +832,19 @@ mod test {
         let app = App::default();
 
         let args = app.sign_licence_form.get_form_fields();
-        let mut f = File::open(&format!("../{}", args[1])).unwrap();
+        let mut f = File::open(format!("../{}", args[1])).unwrap();
         let mut secret_key = "".to_string();
         f.read_to_string(&mut secret_key).unwrap();
         assert!(secret_key.parse::<Issuer>().is_ok());
 
         let args = app.sign_product_passport_form.get_form_fields();
-        let mut f = File::open(&format!("../{}", args[2])).unwrap();
+        let mut f = File::open(format!("../{}", args[2])).unwrap();
+        let mut secret_key = "".to_string();
+        f.read_to_string(&mut secret_key).unwrap();
+        assert!(secret_key.parse::<Issuer>().is_ok());
+
+        let args = app.sign_conflict_zones_form.get_form_fields();
+        let mut f = File::open(format!("../{}", args[1])).unwrap();
         let mut secret_key = "".to_string();
         f.read_to_string(&mut secret_key).unwrap();
         assert!(secret_key.parse::<Issuer>().is_ok());
@@ -646,13 +857,18 @@ mod test {
         let app = App::default();
 
         let args = app.sign_licence_form.get_form_fields();
-        let mut f = File::open(&format!("../{}", args[3])).unwrap();
+        let mut f = File::open(format!("../{}", args[3])).unwrap();
         let mut public_key = "".to_string();
         f.read_to_string(&mut public_key).unwrap();
         assert!(public_key.parse::<Validator>().is_ok());
 
         let args = app.prove_form.get_form_fields();
-        let mut f = File::open(&format!("../{}", args[2])).unwrap();
+        let mut f = File::open(format!("../{}", args[2])).unwrap();
+        let mut public_key = "".to_string();
+        f.read_to_string(&mut public_key).unwrap();
+        assert!(public_key.parse::<Validator>().is_ok());
+
+        let mut f = File::open(format!("../{}", args[4])).unwrap();
         let mut public_key = "".to_string();
         f.read_to_string(&mut public_key).unwrap();
         assert!(public_key.parse::<Validator>().is_ok());
@@ -0,0 +1,181 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use base64::prelude::*;
+use jwt_compact::alg::{Ed25519, Es256, ModulusBits, Rsa, SigningKey};
+use jwt_compact::jwk::JsonWebKey;
+use jwt_compact::Algorithm;
+use jwt_core::SigningAlgorithm;
+use rand_core::{OsRng, RngCore};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("Failed to generate key: {0}")]
+pub struct KeygenError(String);
+
+/// A freshly generated signing key and its public key, both as JWKs.
+pub struct KeyPair {
+    pub secret_key: String,
+    pub public_key: String,
+    /// RFC 7638 thumbprint of the public key, also set as the `kid` of both JWKs
+    pub kid: String,
+}
+
+impl KeyPair {
+    /// Writes the keys to new files, making the secret key readable by the
+    /// owner only.  Existing files are never overwritten.
+    pub fn write(&self, secret_key_path: &Path, public_key_path: &Path) -> std::io::Result<()> {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(secret_key_path)?
+            .write_all(self.secret_key.as_bytes())?;
+
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(public_key_path)?
+            .write_all(self.public_key.as_bytes())
+    }
+}
+
+/// Generates a key pair for `alg`.  RSA keys have a 2048-bit modulus.
+pub fn generate_key_pair(alg: SigningAlgorithm) -> Result<KeyPair, KeygenError> {
+    match alg {
+        SigningAlgorithm::Rs256 | SigningAlgorithm::Ps256 => {
+            let (secret, public) = Rsa::generate(&mut OsRng, ModulusBits::TwoKibibytes)
+                .map_err(|err| KeygenError(err.to_string()))?;
+            Ok(key_pair(
+                JsonWebKey::from(secret.as_ref()),
+                JsonWebKey::from(public.as_ref()),
+                alg,
+            ))
+        }
+        SigningAlgorithm::Es256 => {
+            let secret = random_signing_key::<Es256>()?;
+            Ok(key_pair(
+                JsonWebKey::from(&secret),
+                JsonWebKey::from(&secret.to_verifying_key()),
+                alg,
+            ))
+        }
+        SigningAlgorithm::EdDsa => {
+            let secret = random_signing_key::<Ed25519>()?;
+            Ok(key_pair(
+                JsonWebKey::from(&secret),
+                JsonWebKey::from(&secret.to_verifying_key()),
+                alg,
+            ))
+        }
+    }
+}
+
+/// Draws 32 random bytes until they form a valid key; for P-256 a draw is
+/// only rejected if it exceeds the group order, which is vanishingly rare.
+fn random_signing_key<A>() -> Result<A::SigningKey, KeygenError>
+where
+    A: Algorithm,
+    A::SigningKey: SigningKey<A>,
+{
+    let mut last_err = None;
+    for _ in 0..16 {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        match A::SigningKey::from_slice(&bytes) {
+            Ok(key) => return Ok(key),
+            Err(err) => last_err = Some(err.to_string()),
+        }
+    }
+    Err(KeygenError(last_err.unwrap_or_default()))
+}
+
+fn key_pair(secret: JsonWebKey<'_>, public: JsonWebKey<'_>, alg: SigningAlgorithm) -> KeyPair {
+    let kid = BASE64_URL_SAFE_NO_PAD.encode(public.thumbprint::<Sha256>());
+
+    let annotate = |jwk: JsonWebKey<'_>, key_op: &str| {
+        let mut value = serde_json::to_value(&jwk).expect("JWK should serialise");
+        if let Value::Object(members) = &mut value {
+            members.insert("alg".to_string(), json!(alg.name()));
+            members.insert("use".to_string(), json!("sig"));
+            members.insert("key_ops".to_string(), json!([key_op]));
+            members.insert("kid".to_string(), json!(kid));
+        }
+        serde_json::to_string_pretty(&value).expect("JWK should serialise")
+    };
+
+    KeyPair {
+        secret_key: annotate(secret, "sign"),
+        public_key: annotate(public, "verify"),
+        kid: kid.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jwt_core::{CustomClaims, Issuer, Validator};
+
+    use super::*;
+    use crate::compute_fingerprint;
+
+    fn assert_usable(pair: &KeyPair, alg: SigningAlgorithm) {
+        let issuer = pair.secret_key.parse::<Issuer>().unwrap();
+        let validator = pair.public_key.parse::<Validator>().unwrap();
+        assert_eq!(issuer.algorithm(), alg);
+        assert_eq!(validator.algorithm(), alg);
+
+        let mut claims = CustomClaims::new();
+        claims.add("shipment_id".to_string(), "653321".to_string());
+        let token = issuer.generate_token(&claims).unwrap();
+        assert!(validator.validate_token_integrity(&token).is_ok());
+
+        let public_key: Value = serde_json::from_str(&pair.public_key).unwrap();
+        assert_eq!(public_key["kid"], json!(pair.kid));
+        assert!(public_key.get("d").is_none());
+        assert_eq!(compute_fingerprint(&pair.public_key).unwrap(), pair.kid);
+    }
+
+    #[test]
+    pub fn test_generate_key_pairs() {
+        for alg in [SigningAlgorithm::Es256, SigningAlgorithm::EdDsa] {
+            assert_usable(&generate_key_pair(alg).unwrap(), alg);
+        }
+    }
+
+    #[test]
+    pub fn test_generate_rsa_key_pair() {
+        let pair = generate_key_pair(SigningAlgorithm::Ps256).unwrap();
+        assert_usable(&pair, SigningAlgorithm::Ps256);
+    }
+
+    #[test]
+    pub fn test_write_key_pair() {
+        let dir = std::env::temp_dir().join(format!("keygen-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (secret_path, public_path) = (dir.join("test_sk.jwk"), dir.join("test_pk.jwk"));
+
+        let pair = generate_key_pair(SigningAlgorithm::EdDsa).unwrap();
+        pair.write(&secret_path, &public_path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&secret_path).unwrap(),
+            pair.secret_key
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&secret_path)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        // Keys are never overwritten
+        assert!(pair.write(&secret_path, &public_path).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use jwt_core::PublicOutput;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Reasons a receipt's nullifier is not accepted.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum NullifierError {
+    #[error("The receipt does not commit a nullifier")]
+    Missing,
+
+    #[error("The nullifier was committed for context '{found}' rather than '{expected}'")]
+    WrongContext { expected: String, found: String },
+
+    #[error("The passport has already been used in context '{context}' (nullifier {nullifier})")]
+    AlreadyUsed { context: String, nullifier: String },
+}
+
+/// Verifier-side record of the nullifiers of the receipts it has accepted,
+/// per context, loaded from a JSON file of the form
+/// `{ "contexts": { "<context>": [ "<nullifier>", ... ] } }`.  The store only
+/// ever holds nullifiers, never the shipments behind them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct NullifierStore {
+    #[serde(default)]
+    pub contexts: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl FromStr for NullifierStore {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl NullifierStore {
+    /// Checks that the journal commits a nullifier for `context` that has not
+    /// been recorded yet, and returns it.
+    pub fn check(
+        &self,
+        public_output: &PublicOutput,
+        context: &str,
+    ) -> Result<String, NullifierError> {
+        let claims = &public_output.claims;
+        let (Some(nullifier), Some(committed_context)) =
+            (claims.get("nullifier"), claims.get("nullifier_context"))
+        else {
+            return Err(NullifierError::Missing);
+        };
+        if committed_context != context {
+            return Err(NullifierError::WrongContext {
+                expected: context.to_string(),
+                found: committed_context.to_string(),
+            });
+        }
+        if self
+            .contexts
+            .get(context)
+            .is_some_and(|nullifiers| nullifiers.contains(nullifier))
+        {
+            return Err(NullifierError::AlreadyUsed {
+                context: context.to_string(),
+                nullifier: nullifier.to_string(),
+            });
+        }
+        Ok(nullifier.to_string())
+    }
+
+    /// Checks the journal's nullifier as in [`NullifierStore::check`] and
+    /// records it, so that later receipts for the same passport are rejected.
+    pub fn record(
+        &mut self,
+        public_output: &PublicOutput,
+        context: &str,
+    ) -> Result<String, NullifierError> {
+        let nullifier = self.check(public_output, context)?;
+        self.contexts
+            .entry(context.to_string())
+            .or_default()
+            .insert(nullifier.clone());
+        Ok(nullifier)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jwt_core::{ClaimItem, CustomClaims};
+
+    use super::*;
+
+    fn public_output(claims: &[(&str, &str)]) -> PublicOutput {
+        PublicOutput {
+            fingerprints: vec![],
+            pks: None,
+            claims: CustomClaims {
+                claims: claims
+                    .iter()
+                    .map(|(key, value)| ClaimItem {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    pub fn test_nullifier_store() {
+        let mut store = NullifierStore::default();
+        let receipt = public_output(&[("nullifier", "abc"), ("nullifier_context", "customer-42")]);
+
+        assert_eq!(store.record(&receipt, "customer-42"), Ok("abc".to_string()));
+        assert!(matches!(
+            store.record(&receipt, "customer-42"),
+            Err(NullifierError::AlreadyUsed { .. })
+        ));
+        assert!(matches!(
+            store.check(&receipt, "customer-7"),
+            Err(NullifierError::WrongContext { .. })
+        ));
+        assert_eq!(
+            store.check(&public_output(&[]), "customer-42"),
+            Err(NullifierError::Missing)
+        );
+
+        // The store survives a round trip through its file format
+        let json = serde_json::to_string(&store).unwrap();
+        assert_eq!(json.parse::<NullifierStore>().unwrap(), store);
+        assert_eq!(
+            "{}".parse::<NullifierStore>().unwrap(),
+            NullifierStore::default()
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use jwt_core::CustomClaims;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("Failed to parse policy: {0}")]
+pub struct PolicyError(String);
+
+/// A value a claim is compared with.  Claims are committed as strings, so
+/// booleans and numbers are compared by their string form.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ClaimValue {
+    Bool(bool),
+    Integer(i64),
+    Text(String),
+}
+
+impl fmt::Display for ClaimValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClaimValue::Bool(value) => write!(f, "{value}"),
+            ClaimValue::Integer(value) => write!(f, "{value}"),
+            ClaimValue::Text(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// What a rule requires of its claim.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// The claim is committed, whatever its value
+    Present(bool),
+    Equals(ClaimValue),
+    OneOf(Vec<ClaimValue>),
+    /// The claim is an integer no smaller than this
+    AtLeast(i64),
+    /// The claim is an integer no larger than this
+    AtMost(i64),
+    /// The claim is a date no more than this many days before the time of
+    /// verification, and not after it
+    WithinDays(u32),
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Present(true) => write!(f, "is present"),
+            Condition::Present(false) => write!(f, "is absent"),
+            Condition::Equals(value) => write!(f, "== {value}"),
+            Condition::OneOf(values) => {
+                let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "is one of [{}]", values.join(", "))
+            }
+            Condition::AtLeast(min) => write!(f, ">= {min}"),
+            Condition::AtMost(max) => write!(f, "<= {max}"),
+            Condition::WithinDays(days) => write!(f, "is within the last {days} days"),
+        }
+    }
+}
+
+/// A requirement on one journal claim, e.g. in TOML:
+///
+/// ```toml
+/// [[rules]]
+/// claim = "conflict_zones_version"
+/// at_least = 3
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub claim: String,
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// Shown in the report instead of the rule itself
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Outcome of evaluating one [`Rule`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RuleResult {
+    pub rule: String,
+    pub claim: String,
+    /// The committed value of the claim, if any
+    pub actual: Option<String>,
+    pub passed: bool,
+    /// Why the rule failed
+    pub reason: Option<String>,
+}
+
+/// Per-rule report of evaluating a [`Policy`] against journal claims.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PolicyReport {
+    /// Whether every rule passed
+    pub passed: bool,
+    pub rules: Vec<RuleResult>,
+}
+
+impl fmt::Display for PolicyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = self
+            .rules
+            .iter()
+            .map(|result| match &result.reason {
+                None => format!("PASS {}", result.rule),
+                Some(reason) => format!("FAIL {}: {reason}", result.rule),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Declarative verifier policy: a list of rules that must all hold for the
+/// claims committed to the journal.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    pub rules: Vec<Rule>,
+}
+
+impl Policy {
+    pub fn from_toml(policy: &str) -> Result<Self, PolicyError> {
+        toml::from_str(policy).map_err(|err| PolicyError(err.to_string()))
+    }
+
+    pub fn from_json(policy: &str) -> Result<Self, PolicyError> {
+        serde_json::from_str(policy).map_err(|err| PolicyError(err.to_string()))
+    }
+
+    /// Evaluates every rule against `claims`; dates are compared with `now`.
+    pub fn evaluate(&self, claims: &CustomClaims, now: DateTime<Utc>) -> PolicyReport {
+        let rules = self
+            .rules
+            .iter()
+            .map(|rule| {
+                let actual = claims.get(&rule.claim);
+                let reason = rule.check(actual, now).err();
+                RuleResult {
+                    rule: rule.to_string(),
+                    claim: rule.claim.clone(),
+                    actual: actual.map(str::to_string),
+                    passed: reason.is_none(),
+                    reason,
+                }
+            })
+            .collect::<Vec<RuleResult>>();
+        PolicyReport {
+            passed: rules.iter().all(|result| result.passed),
+            rules,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.description {
+            Some(description) => write!(f, "{description}"),
+            None => write!(f, "{} {}", self.claim, self.condition),
+        }
+    }
+}
+
+impl Rule {
+    fn check(&self, actual: Option<&str>, now: DateTime<Utc>) -> Result<(), String> {
+        let actual = match (&self.condition, actual) {
+            (Condition::Present(expected), actual) => {
+                return match (expected, actual) {
+                    (true, None) => Err("the claim is not committed".to_string()),
+                    (false, Some(_)) => Err("the claim is committed".to_string()),
+                    _ => Ok(()),
+                };
+            }
+            (_, Some(actual)) => actual,
+            (_, None) => return Err("the claim is not committed".to_string()),
+        };
+
+        match &self.condition {
+            Condition::Present(_) => Ok(()),
+            Condition::Equals(expected) => {
+                if actual == expected.to_string() {
+                    Ok(())
+                } else {
+                    Err(format!("expected {expected}, got {actual}"))
+                }
+            }
+            Condition::OneOf(expected) => {
+                if expected.iter().any(|value| actual == value.to_string()) {
+                    Ok(())
+                } else {
+                    Err(format!("{actual} is not an accepted value"))
+                }
+            }
+            Condition::AtLeast(min) => match parse_integer(actual)? {
+                value if value >= *min => Ok(()),
+                value => Err(format!("{value} is less than {min}")),
+            },
+            Condition::AtMost(max) => match parse_integer(actual)? {
+                value if value <= *max => Ok(()),
+                value => Err(format!("{value} is greater than {max}")),
+            },
+            Condition::WithinDays(days) => {
+                let date = actual
+                    .parse::<DateTime<Utc>>()
+                    .map_err(|err| format!("{actual} is not a date: {err}"))?;
+                if date > now {
+                    Err(format!("{actual} is in the future"))
+                } else if now - date > Duration::days(i64::from(*days)) {
+                    Err(format!("{actual} is more than {days} days ago"))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+fn parse_integer(value: &str) -> Result<i64, String> {
+    value
+        .parse::<i64>()
+        .map_err(|err| format!("{value} is not an integer: {err}"))
+}
+
+#[cfg(test)]
+mod test {
+    use jwt_core::ClaimItem;
+
+    use super::*;
+
+    fn claims() -> CustomClaims {
+        let claim = |key: &str, value: &str| ClaimItem {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        CustomClaims {
+            claims: vec![
+                claim("shipment_id", "653321"),
+                claim("issue_date", "2025-12-01T00:00:00Z"),
+                claim("licence_valid_when_signing_product_passport", "true"),
+                claim("country_of_operation", "GB"),
+                claim("conflict_zones_version", "1"),
+            ],
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2026-01-15T00:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    pub fn test_policy_test_data() {
+        let policy = Policy::from_toml(include_str!("../../test_data/policy.toml")).unwrap();
+        let report = policy.evaluate(&claims(), now());
+        assert!(report.passed, "{report}");
+        assert_eq!(report.rules.len(), policy.rules.len());
+
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(Policy::from_json(&json).unwrap(), policy);
+    }
+
+    #[test]
+    pub fn test_policy_rules() {
+        let policy = Policy::from_json(
+            r#"{"rules": [
+                {"claim": "licence_valid_when_signing_product_passport", "equals": false},
+                {"claim": "issue_date", "within_days": 30},
+                {"claim": "conflict_zones_version", "at_least": 2, "description": "Recent conflict zone list"},
+                {"claim": "conflict_zones_version", "at_most": 1},
+                {"claim": "country_of_operation", "one_of": ["FR", "DE"]},
+                {"claim": "final_holder_id", "present": true},
+                {"claim": "shipment_id", "at_least": 1}
+            ]}"#,
+        )
+        .unwrap();
+        let report = policy.evaluate(&claims(), now());
+        assert!(!report.passed);
+
+        let reasons = report
+            .rules
+            .iter()
+            .map(|result| result.reason.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![
+                Some("expected false, got true"),
+                Some("2025-12-01T00:00:00Z is more than 30 days ago"),
+                Some("1 is less than 2"),
+                None,
+                Some("GB is not an accepted value"),
+                Some("the claim is not committed"),
+                None,
+            ]
+        );
+        assert_eq!(report.rules[2].rule, "Recent conflict zone list");
+        assert_eq!(report.rules[3].rule, "conflict_zones_version <= 1");
+        assert_eq!(report.rules[5].actual, None);
+    }
+
+    #[test]
+    pub fn test_invalid_policy() {
+        assert!(Policy::from_toml("[[rules]]\nclaim = \"issue_date\"\nbefore = 3").is_err());
+        assert!(Policy::from_json(r#"{"rules": [{"equals": "true"}]}"#).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use jwt_core::{format_date, PublicOutput};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// What an authority is trusted to sign.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorityRole {
+    /// Signs mining licences
+    MiningAuthority,
+    /// Signs conflict zone lists
+    ConflictZoneAuthority,
+}
+
+impl fmt::Display for AuthorityRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthorityRole::MiningAuthority => write!(f, "national mining authority"),
+            AuthorityRole::ConflictZoneAuthority => write!(f, "conflict zone authority"),
+        }
+    }
+}
+
+/// A verifier's entry for an authority key it trusts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TrustedAuthority {
+    pub name: String,
+    pub role: AuthorityRole,
+    /// RFC 7638 thumbprint of the authority's public key
+    pub fingerprint: String,
+    pub valid_from: DateTime<Utc>,
+    /// Open-ended if absent
+    #[serde(default)]
+    pub valid_until: Option<DateTime<Utc>>,
+    /// Countries of operation a mining authority may licence; any if empty
+    #[serde(default)]
+    pub jurisdictions: Vec<String>,
+}
+
+/// Reasons a committed authority key is not trusted.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TrustError {
+    #[error("Key {fingerprint} is not a trusted {role}")]
+    UnknownAuthority {
+        role: AuthorityRole,
+        fingerprint: String,
+    },
+
+    #[error("{name} was not yet trusted on {date}")]
+    NotYetValid { name: String, date: String },
+
+    #[error("{name} was no longer trusted on {date}")]
+    Expired { name: String, date: String },
+
+    #[error("{name} may not licence mining in {country}")]
+    OutsideJurisdiction { name: String, country: String },
+
+    #[error("The receipt does not commit the claim '{0}'")]
+    MissingClaim(&'static str),
+
+    #[error("Invalid date in claim '{key}': {reason}")]
+    InvalidDate { key: &'static str, reason: String },
+}
+
+/// Outcome of checking one committed authority key against the registry.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuthorityCheck {
+    pub role: AuthorityRole,
+    pub fingerprint: Option<String>,
+    /// Name of the registry entry that matched
+    pub name: Option<String>,
+    pub trusted: bool,
+    /// Why the key is not trusted
+    pub reason: Option<String>,
+}
+
+/// Machine-readable result of checking a receipt against a [`TrustRegistry`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    /// Whether every committed authority key is trusted
+    pub trusted: bool,
+    pub authorities: Vec<AuthorityCheck>,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reasons = self
+            .authorities
+            .iter()
+            .filter_map(|check| check.reason.as_deref())
+            .collect::<Vec<_>>();
+        if reasons.is_empty() {
+            write!(f, "All authority keys are trusted")
+        } else {
+            write!(f, "{}", reasons.join("; "))
+        }
+    }
+}
+
+/// Verifier-side trust store of authority keys, loaded from a JSON file of the
+/// form `{ "authorities": [ ... ] }`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustRegistry {
+    pub authorities: Vec<TrustedAuthority>,
+}
+
+impl FromStr for TrustRegistry {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl TrustRegistry {
+    /// Checks that the national mining authority key committed to the journal
+    /// was trusted on the passport signing date for the licence's country of
+    /// operation, and that the conflict zone authority key was trusted on the
+    /// list's publication date.  The signing date is committed whatever the
+    /// prover chose to disclose of the passport.
+    pub fn check(&self, public_output: &PublicOutput) -> Verdict {
+        let authorities = vec![
+            self.check_authority(
+                public_output,
+                AuthorityRole::MiningAuthority,
+                0,
+                "passport_signing_date",
+                public_output.claims.get("country_of_operation"),
+            ),
+            self.check_authority(
+                public_output,
+                AuthorityRole::ConflictZoneAuthority,
+                1,
+                "conflict_zones_publication_date",
+                None,
+            ),
+        ];
+        Verdict {
+            trusted: authorities.iter().all(|check| check.trusted),
+            authorities,
+        }
+    }
+
+    fn check_authority(
+        &self,
+        public_output: &PublicOutput,
+        role: AuthorityRole,
+        index: usize,
+        date_claim: &'static str,
+        country: Option<&str>,
+    ) -> AuthorityCheck {
+        let fingerprint = public_output.fingerprints.get(index).cloned();
+        let result = match &fingerprint {
+            Some(fingerprint) => committed_date(public_output, date_claim)
+                .and_then(|date| self.find(role, fingerprint, &date, country)),
+            None => Err(TrustError::UnknownAuthority {
+                role,
+                fingerprint: String::new(),
+            }),
+        };
+        match result {
+            Ok(authority) => AuthorityCheck {
+                role,
+                fingerprint,
+                name: Some(authority.name.clone()),
+                trusted: true,
+                reason: None,
+            },
+            Err(err) => AuthorityCheck {
+                role,
+                fingerprint,
+                name: None,
+                trusted: false,
+                reason: Some(err.to_string()),
+            },
+        }
+    }
+
+    /// Finds an entry for the key that is active on `date`.  A key may have
+    /// several entries, e.g. after a change of jurisdiction; if none applies,
+    /// the first entry's failure is reported.
+    fn find(
+        &self,
+        role: AuthorityRole,
+        fingerprint: &str,
+        date: &DateTime<Utc>,
+        country: Option<&str>,
+    ) -> Result<&TrustedAuthority, TrustError> {
+        let mut first_err = None;
+        for authority in self
+            .authorities
+            .iter()
+            .filter(|authority| authority.role == role && authority.fingerprint == fingerprint)
+        {
+            match authority.admits(date, country) {
+                Ok(()) => return Ok(authority),
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        Err(first_err.unwrap_or_else(|| TrustError::UnknownAuthority {
+            role,
+            fingerprint: fingerprint.to_string(),
+        }))
+    }
+}
+
+impl TrustedAuthority {
+    fn admits(&self, date: &DateTime<Utc>, country: Option<&str>) -> Result<(), TrustError> {
+        if *date < self.valid_from {
+            return Err(TrustError::NotYetValid {
+                name: self.name.clone(),
+                date: format_date(date),
+            });
+        }
+        if self
+            .valid_until
+            .is_some_and(|valid_until| *date > valid_until)
+        {
+            return Err(TrustError::Expired {
+                name: self.name.clone(),
+                date: format_date(date),
+            });
+        }
+        if self.role == AuthorityRole::MiningAuthority && !self.jurisdictions.is_empty() {
+            let countries = country.ok_or(TrustError::MissingClaim("country_of_operation"))?;
+            // Blended shipments commit every country they were sourced from
+            if let Some(country) = countries.split(',').find(|country| {
+                !self
+                    .jurisdictions
+                    .iter()
+                    .any(|jurisdiction| jurisdiction == country)
+            }) {
+                return Err(TrustError::OutsideJurisdiction {
+                    name: self.name.clone(),
+                    country: country.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn committed_date(
+    public_output: &PublicOutput,
+    key: &'static str,
+) -> Result<DateTime<Utc>, TrustError> {
+    public_output
+        .claims
+        .get(key)
+        .ok_or(TrustError::MissingClaim(key))?
+        .parse::<DateTime<Utc>>()
+        .map_err(|err| TrustError::InvalidDate {
+            key,
+            reason: err.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use jwt_core::{ClaimItem, CustomClaims};
+
+    use super::*;
+
+    const MINING_AUTHORITY: &str = "dN9WQslykzb1chJSuteUpchRYCQJsrU6GMj4-N8y52g";
+    const CONFLICT_ZONE_AUTHORITY: &str = "iwjCCIP9VwfdxtMAztwRCpQlHZbQRJnicXFfoHbFvOk";
+
+    fn public_output(country: &str, signing_date: &str) -> PublicOutput {
+        let claim = |key: &str, value: &str| ClaimItem {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        PublicOutput {
+            fingerprints: vec![
+                MINING_AUTHORITY.to_string(),
+                CONFLICT_ZONE_AUTHORITY.to_string(),
+            ],
+            pks: None,
+            claims: CustomClaims {
+                claims: vec![
+                    claim("passport_signing_date", signing_date),
+                    claim("country_of_operation", country),
+                    claim("conflict_zones_publication_date", "2025-06-01T00:00:00Z"),
+                ],
+            },
+        }
+    }
+
+    fn registry() -> TrustRegistry {
+        include_str!("../../test_data/trust_registry.json")
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    pub fn test_trusted_authorities() {
+        let verdict = registry().check(&public_output("GB", "2025-12-01T00:00:00Z"));
+        assert!(verdict.trusted, "{verdict}");
+        assert_eq!(
+            verdict.authorities[0].name.as_deref(),
+            Some("National Mining Authority")
+        );
+        assert_eq!(
+            verdict.authorities[1].name.as_deref(),
+            Some("Conflict Zone Authority")
+        );
+    }
+
+    #[test]
+    pub fn test_untrusted_authorities() {
+        let verdict = registry().check(&public_output("FR", "2025-12-01T00:00:00Z"));
+        assert!(!verdict.trusted);
+        assert!(verdict.authorities[1].trusted);
+        assert_eq!(
+            verdict.authorities[0].reason.as_deref(),
+            Some("National Mining Authority may not licence mining in FR")
+        );
+        // Every country of a blended shipment must be within the jurisdiction
+        let verdict = registry().check(&public_output("FR,GB", "2025-12-01T00:00:00Z"));
+        assert_eq!(
+            verdict.authorities[0].reason.as_deref(),
+            Some("National Mining Authority may not licence mining in FR")
+        );
+
+        let verdict = registry().check(&public_output("GB", "2023-12-01T00:00:00Z"));
+        assert_eq!(
+            verdict.authorities[0].reason.as_deref(),
+            Some("National Mining Authority was not yet trusted on 2023-12-01T00:00:00Z")
+        );
+
+        let verdict = registry().check(&public_output("GB", "2031-01-01T00:00:00Z"));
+        assert_eq!(
+            verdict.authorities[0].reason.as_deref(),
+            Some("National Mining Authority was no longer trusted on 2031-01-01T00:00:00Z")
+        );
+
+        // Keys are only trusted in their own role
+        let mut swapped = public_output("GB", "2025-12-01T00:00:00Z");
+        swapped.fingerprints.reverse();
+        let verdict = registry().check(&swapped);
+        assert!(verdict.authorities.iter().all(|check| !check.trusted));
+        assert_eq!(
+            verdict.authorities[0].reason,
+            Some(format!(
+                "Key {CONFLICT_ZONE_AUTHORITY} is not a trusted national mining authority"
+            ))
+        );
+
+        // Receipts that do not commit the country cannot be checked against a jurisdiction
+        let mut without_country = public_output("GB", "2025-12-01T00:00:00Z");
+        without_country
+            .claims
+            .claims
+            .retain(|claim| claim.key != "country_of_operation");
+        let verdict = registry().check(&without_country);
+        assert_eq!(
+            verdict.authorities[0].reason.as_deref(),
+            Some("The receipt does not commit the claim 'country_of_operation'")
+        );
+
+        // The passport's own issue date is not used, as the prover may hide it
+        let mut without_signing_date = public_output("GB", "2025-12-01T00:00:00Z");
+        without_signing_date.claims.claims[0].key = "issue_date".to_string();
+        let verdict = registry().check(&without_signing_date);
+        assert_eq!(
+            verdict.authorities[0].reason.as_deref(),
+            Some("The receipt does not commit the claim 'passport_signing_date'")
+        );
+
+        let verdict = TrustRegistry::default().check(&public_output("GB", "2025-12-01T00:00:00Z"));
+        assert!(!verdict.trusted);
+    }
+
+    #[test]
+    pub fn test_verdict_json() {
+        let verdict = registry().check(&public_output("FR", "2025-12-01T00:00:00Z"));
+        let json = serde_json::to_value(&verdict).unwrap();
+        assert_eq!(json["trusted"], false);
+        assert_eq!(json["authorities"][0]["role"], "mining_authority");
+        assert_eq!(json["authorities"][0]["fingerprint"], MINING_AUTHORITY);
+        assert_eq!(json["authorities"][1]["trusted"], true);
+    }
+}
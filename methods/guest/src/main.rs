@@ -16,133 +16,394 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use chrono::{DateTime, Utc};
-use jwt_core::{ClaimItem, ConflictZones, CustomClaims, PublicOutput, Validator};
+use jwt_core::{
+    format_date, jwk_thumbprint, nullifier, token_digest, ClaimItem, ConflictZoneList,
+    ConflictZoneRoot, CustodyTransfer, CustomClaims, DisclosureSet, LicenceStatusList,
+    MiningLicence, ProductPassport, ProofOptions, PublicOutput, RecallList, Validator, ZoneMatch,
+    ZoneWitness,
+};
 use risc0_zkvm::guest::env;
 
 fn main() {
     // Read the token input
     let product_passport: String = env::read();
-    let mining_licence: String = env::read();
+    let mining_licences: Vec<String> = env::read();
     let nma_public_key: String = env::read();
-    let conflict_zones_str: String = env::read();
+    let conflict_zone_list: String = env::read();
+    let conflict_zone_authority_public_key: String = env::read();
+    let conflict_zone_witnesses: Option<Vec<String>> = env::read();
+    let custody_chain: Vec<String> = env::read();
+    let options: ProofOptions = env::read();
+    let disclosure_seed: String = env::read();
 
     let validator = nma_public_key
         .parse::<Validator>()
         .expect("Could not parse validator from public key");
-    let valid_licence = validator
-        .validate_token_integrity(&mining_licence)
-        .expect("Licence validation failed");
-
-    let conflict_zones: ConflictZones = serde_json::from_str(&conflict_zones_str).unwrap();
-
-    let country_of_operation = valid_licence
-        .claims()
-        .custom
-        .claims
-        .iter()
-        .find(|claim| claim.key == "country_of_operation")
-        .expect("Could not find country")
-        .value
-        .to_string();
-    let region_of_operation = valid_licence
-        .claims()
-        .custom
-        .claims
+    if mining_licences.is_empty() {
+        panic!("Licence validation failed: no mining licence was given")
+    }
+    let valid_licences: Vec<MiningLicence> = mining_licences
         .iter()
-        .find(|claim| claim.key == "region_of_operation")
-        .expect("Could not find country")
-        .value
-        .to_string();
-    for zone in conflict_zones.zones.iter() {
-        if zone.country == country_of_operation && zone.region == region_of_operation {
-            panic!("Mining licence indicates work in conflict zone!")
+        .map(|licence| {
+            MiningLicence::verify(licence, &validator)
+                .unwrap_or_else(|err| panic!("Licence validation failed: {err}"))
+        })
+        .collect();
+
+    // Revocation is checked against the authority's own status list, which is
+    // large enough that reading it does not reveal which licence was checked
+    let licence_status_list = options.licence_status_list.as_ref().map(|token| {
+        LicenceStatusList::verify(token, &validator)
+            .unwrap_or_else(|err| panic!("Licence status list validation failed: {err}"))
+    });
+    if let Some(status_list) = licence_status_list.as_ref() {
+        for licence in valid_licences.iter() {
+            let status_index = licence.status_index.unwrap_or_else(|| {
+                panic!("Licence status list validation failed: licence has no status index")
+            });
+            let revoked = status_list
+                .is_revoked(status_index)
+                .unwrap_or_else(|err| panic!("Licence status list validation failed: {err}"));
+            if revoked {
+                panic!("Mining licence has been revoked!")
+            }
         }
     }
 
-    let mining_company_validator = valid_licence
-        .claims()
-        .custom
-        .claims
-        .iter()
-        .find(|claim| claim.key == "subject_pk".to_string())
-        .expect("Could not find public key in licence")
-        .value
-        .to_string()
+    // The conflict zone list must be signed by the authority key committed to the journal
+    let conflict_zone_authority_validator = conflict_zone_authority_public_key
         .parse::<Validator>()
         .expect("Could not parse validator from public key");
 
-    let valid_passport = mining_company_validator
-        .validate_token_integrity(&product_passport)
-        .expect("Passport validation failed");
+    // Both sides were normalised to ISO 3166 codes when verified, so a region
+    // cannot escape a match by being named differently, and a zone covers every
+    // subdivision within it.  Every licence of a blended shipment is checked.
+    // Only the full list holds zone areas to check licence sites against
+    let sites_checked = conflict_zone_witnesses.is_none();
+    let conflict_zone_claims = match conflict_zone_witnesses {
+        None => {
+            let valid_conflict_zone_list =
+                ConflictZoneList::verify(&conflict_zone_list, &conflict_zone_authority_validator)
+                    .unwrap_or_else(|err| panic!("Conflict zone list validation failed: {err}"));
+            let conflict_zones = valid_conflict_zone_list.conflict_zones;
 
-    // Check passport was issued when mining licence was valid
+            for licence in valid_licences.iter() {
+                if let Some((_, kind)) = conflict_zones
+                    .find_match(&licence.country_of_operation, &licence.region_of_operation)
+                {
+                    panic!("Mining licence indicates work in conflict zone! (matched {kind} rule)")
+                }
 
-    let licence_issue_date = valid_licence
-        .claims()
-        .custom
-        .claims
-        .iter()
-        .find(|claim| claim.key == "issue_date".to_string())
-        .expect("Could not find issue date in licence");
-    let licence_issue_date = licence_issue_date.value.parse::<DateTime<Utc>>().unwrap();
-
-    let licence_expiry_date = valid_licence
-        .claims()
-        .custom
-        .claims
-        .iter()
-        .find(|claim| claim.key == "expiry_date".to_string())
-        .expect("Could not find expiry date in licence");
-    let licence_expiry_date = licence_expiry_date.value.parse::<DateTime<Utc>>().unwrap();
-
-    let passport_issue_date_claimitem = valid_passport
-        .claims()
-        .custom
-        .claims
+                // Sites are checked against the zones' areas, so a region cannot be dodged by spelling
+                for site in licence.sites.iter() {
+                    if conflict_zones.find_area_match(site).is_some() {
+                        panic!(
+                            "Mining licence site lies within a conflict zone! (matched {} rule)",
+                            ZoneMatch::Area
+                        )
+                    }
+                }
+            }
+
+            [
+                ("conflict_zones_digest", conflict_zones.digest()),
+                ("conflict_zones_version", conflict_zones.version.to_string()),
+                (
+                    "conflict_zones_publication_date",
+                    format_date(&valid_conflict_zone_list.publication_date),
+                ),
+            ]
+        }
+        Some(witnesses) => {
+            // Only the zones either side of each licence's country and regions
+            // are read, rather than the whole list
+            let valid_root =
+                ConflictZoneRoot::verify(&conflict_zone_list, &conflict_zone_authority_validator)
+                    .unwrap_or_else(|err| panic!("Conflict zone list validation failed: {err}"));
+            if witnesses.len() != valid_licences.len() {
+                panic!("Conflict zone witness validation failed: expected one witness per licence")
+            }
+
+            for (licence, witness) in valid_licences.iter().zip(witnesses.iter()) {
+                let zone_match = witness
+                    .parse::<ZoneWitness>()
+                    .and_then(|witness| {
+                        witness.check(
+                            &valid_root.root,
+                            valid_root.zone_count,
+                            &licence.country_of_operation,
+                            &licence.region_of_operation,
+                        )
+                    })
+                    .unwrap_or_else(|err| panic!("Conflict zone witness validation failed: {err}"));
+                if let Some(kind) = zone_match {
+                    panic!("Mining licence indicates work in conflict zone! (matched {kind} rule)")
+                }
+            }
+
+            [
+                ("conflict_zones_root", valid_root.root),
+                ("conflict_zones_version", valid_root.version.to_string()),
+                (
+                    "conflict_zones_publication_date",
+                    format_date(&valid_root.publication_date),
+                ),
+            ]
+        }
+    };
+    let sites_outside_conflict_zones = sites_checked
+        && valid_licences
+            .iter()
+            .all(|licence| !licence.sites.is_empty());
+
+    // A blended shipment is passported by the one company holding every licence
+    let mining_company_fingerprint = |licence: &MiningLicence| {
+        jwk_thumbprint(&licence.subject_pk)
+            .expect("Could not compute mining company key thumbprint")
+    };
+    let valid_licence = &valid_licences[0];
+    let mining_company = mining_company_fingerprint(valid_licence);
+    if valid_licences[1..]
         .iter()
-        .find(|claim| claim.key == "issue_date")
-        .expect("Could not find issue_date");
-    let passport_issue_date = passport_issue_date_claimitem
-        .value
-        .parse::<DateTime<Utc>>()
-        .unwrap();
-
-    let mut licence_valid_when_signing_product_passport = true;
-    if passport_issue_date.cmp(&licence_issue_date).is_lt()
-        || passport_issue_date.cmp(&licence_expiry_date).is_gt()
+        .any(|licence| mining_company_fingerprint(licence) != mining_company)
     {
-        licence_valid_when_signing_product_passport = false;
+        panic!("Mining licences are held by different companies!")
     }
 
-    let public_output = PublicOutput {
-        pks: vec![nma_public_key],
-        claims: CustomClaims {
-            claims: [
-                ClaimItem {
-                    key: "shipment_id".to_string(),
-                    value: valid_passport
-                        .claims()
-                        .custom
-                        .claims
-                        .iter()
-                        .find(|claim| claim.key == "shipment_id")
-                        .expect("Could not find shipment_id")
-                        .value
-                        .to_string(),
-                },
-                passport_issue_date_claimitem.clone(),
-                ClaimItem {
-                    key: "licence_valid_when_signing_product_passport".to_string(),
-                    value: licence_valid_when_signing_product_passport.to_string(),
-                },
-                ClaimItem {
-                    key: "not_operating_in_following_zones".to_string(),
-                    value: conflict_zones_str,
-                },
-            ]
-            .to_vec(),
+    let mining_company_validator = valid_licence
+        .subject_validator()
+        .expect("Could not parse validator from public key");
+
+    let valid_passport = ProductPassport::verify(&product_passport, &mining_company_validator)
+        .unwrap_or_else(|err| panic!("Passport validation failed: {err}"));
+
+    // A recall list is signed by the same company key as the passport, and
+    // must have been published after it for its absence to mean anything
+    let recall_list = options.recall_list.as_ref().map(|token| {
+        RecallList::verify(token, &mining_company_validator)
+            .unwrap_or_else(|err| panic!("Recall list validation failed: {err}"))
+    });
+    if let Some(recall_list) = recall_list.as_ref() {
+        if recall_list.publication_date < valid_passport.issue_date {
+            panic!("Recall list validation failed: list predates the product passport")
+        }
+        if recall_list.is_recalled(&valid_passport.shipment_id) {
+            panic!("Product passport has been recalled!")
+        }
+    }
+
+    // Each licence must be a declared source, and the sources must account for
+    // the whole declared mass
+    if valid_passport.sources.is_empty() {
+        if mining_licences.len() != 1 {
+            panic!("Passport sources do not match the mining licences!")
+        }
+    } else {
+        let mut unmatched: Vec<String> = mining_licences
+            .iter()
+            .map(|licence| token_digest(licence))
+            .collect();
+        for source in valid_passport.sources.iter() {
+            if source.mass_kg == 0 {
+                panic!("Passport sources include a licence with no mass!")
+            }
+            match unmatched
+                .iter()
+                .position(|digest| *digest == source.licence_digest)
+            {
+                Some(index) => {
+                    unmatched.swap_remove(index);
+                }
+                None => panic!("Passport sources do not match the mining licences!"),
+            }
+        }
+        if !unmatched.is_empty() {
+            panic!("Passport sources do not match the mining licences!")
+        }
+        let total_mass_kg = valid_passport
+            .sources
+            .iter()
+            .try_fold(0u64, |total, source| total.checked_add(source.mass_kg));
+        if total_mass_kg != valid_passport.declared_mass_kg {
+            panic!("Passport sources do not add up to the declared mass!")
+        }
+    }
+
+    // Follow custody of the shipment from the mining company to its final holder
+    let mut holder_validator = mining_company_validator;
+    let mut last_transfer: Option<CustodyTransfer> = None;
+    for custody_token in custody_chain.iter() {
+        let transfer = CustodyTransfer::verify(custody_token, &holder_validator)
+            .unwrap_or_else(|err| panic!("Custody transfer validation failed: {err}"));
+        if transfer.shipment_id != valid_passport.shipment_id {
+            panic!("Custody transfer is for a different shipment!")
+        }
+        let previous_date = last_transfer
+            .as_ref()
+            .map_or(valid_passport.issue_date, |previous| previous.transfer_date);
+        if transfer.transfer_date < previous_date {
+            panic!("Custody transfer predates the previous link in the chain!")
+        }
+        holder_validator = transfer
+            .subject_validator()
+            .expect("Could not parse validator from public key");
+        last_transfer = Some(transfer);
+    }
+
+    // Check passport was issued when every mining licence was valid
+    let licence_valid_when_signing_product_passport = valid_licences.iter().all(|licence| {
+        licence.issue_date <= valid_passport.issue_date
+            && valid_passport.issue_date <= licence.expiry_date
+    });
+    if options.strict && !licence_valid_when_signing_product_passport {
+        panic!("Product passport was issued outside the mining licence validity period!")
+    }
+
+    // Blended shipments may come from several countries, listed once each
+    let mut countries: Vec<&str> = valid_licences
+        .iter()
+        .map(|licence| licence.country_of_operation.as_str())
+        .collect();
+    countries.sort();
+    countries.dedup();
+    let countries_of_operation = countries.join(",");
+
+    // The prover chooses which passport claims are revealed, committed to or
+    // hidden, but must account for every one of them
+    let passport_claims = CustomClaims::from(&valid_passport);
+    let disclosure = options
+        .disclosure
+        .clone()
+        .unwrap_or_else(|| DisclosureSet::standard(&passport_claims));
+    let mut public_claims: CustomClaims = disclosure
+        .apply(&disclosure_seed, &passport_claims)
+        .unwrap_or_else(|err| panic!("Disclosure validation failed: {err}"))
+        .into_iter()
+        .collect();
+    if options.disclosure.is_some() {
+        public_claims.add("disclosure".to_string(), disclosure.canonical_json());
+    }
+
+    // Only each statement and whether it holds are committed, not the figures,
+    // and a statement given twice is committed once
+    for predicate in options.predicates.iter() {
+        let holds = predicate
+            .evaluate(&passport_claims)
+            .unwrap_or_else(|err| panic!("Predicate evaluation failed: {err}"));
+        let key = predicate.journal_key();
+        if public_claims.get(&key).is_none() {
+            public_claims.add(key, holds.to_string());
+        }
+    }
+    public_claims.claims.extend([
+        // Lets verifiers check the licence falls within the authority's jurisdiction
+        ClaimItem {
+            key: "country_of_operation".to_string(),
+            value: countries_of_operation,
+        },
+        ClaimItem {
+            key: "licence_valid_when_signing_product_passport".to_string(),
+            value: licence_valid_when_signing_product_passport.to_string(),
+        },
+        // Committed whatever the disclosure set, so verifiers can always check
+        // the authorities were trusted when the passport was signed
+        ClaimItem {
+            key: "passport_signing_date".to_string(),
+            value: format_date(&valid_passport.issue_date),
+        },
+        // Only whether the sites avoid every zone is disclosed, not where they are
+        ClaimItem {
+            key: "sites_outside_conflict_zones".to_string(),
+            value: sites_outside_conflict_zones.to_string(),
+        },
+        ClaimItem {
+            key: "strict_mode".to_string(),
+            value: options.strict.to_string(),
         },
+    ]);
+    for (key, value) in conflict_zone_claims {
+        public_claims.add(key.to_string(), value);
+    }
+
+    // Any licence in a conflict zone has aborted the proof, so all of the
+    // declared mass comes from non-conflict licences.  The declared mass itself
+    // is a passport claim, disclosed like any other.
+    public_claims.add("source_count".to_string(), valid_licences.len().to_string());
+    public_claims.add("non_conflict_mass_percent".to_string(), "100".to_string());
+
+    // The publication date lets verifiers decide whether the list was recent enough
+    public_claims.add(
+        "licence_status_checked".to_string(),
+        licence_status_list.is_some().to_string(),
+    );
+    if let Some(status_list) = licence_status_list {
+        public_claims.add(
+            "licence_status_publication_date".to_string(),
+            format_date(&status_list.publication_date),
+        );
+    }
+
+    public_claims.add(
+        "recall_checked".to_string(),
+        recall_list.is_some().to_string(),
+    );
+    if let Some(recall_list) = recall_list {
+        public_claims.add(
+            "recall_list_publication_date".to_string(),
+            format_date(&recall_list.publication_date),
+        );
+    }
+
+    // The nullifier repeats whenever this passport is proved in the same
+    // context, without revealing which passport it is
+    if let Some(context) = options.nullifier_context {
+        public_claims.add(
+            "nullifier".to_string(),
+            nullifier(&product_passport, &context).expect("Could not derive nullifier"),
+        );
+        public_claims.add("nullifier_context".to_string(), context);
+    }
+
+    // Only the final holder of the shipment is disclosed, not the intermediate ones
+    if let Some(final_transfer) = last_transfer {
+        public_claims.add("final_holder_id".to_string(), final_transfer.subject_id);
+        let final_holder_fingerprint = jwk_thumbprint(&final_transfer.subject_pk)
+            .expect("Could not compute final holder key thumbprint");
+        public_claims.add(
+            "final_holder_fingerprint".to_string(),
+            final_holder_fingerprint,
+        );
+        if options.commit_full_keys {
+            public_claims.add("final_holder_pk".to_string(), final_transfer.subject_pk);
+        }
+        public_claims.add(
+            "custody_transfer_date".to_string(),
+            format_date(&final_transfer.transfer_date),
+        );
+    }
+
+    // Verifiers read the first claim under each key, so none may be committed twice
+    for (i, claim) in public_claims.claims.iter().enumerate() {
+        if public_claims.claims[..i]
+            .iter()
+            .any(|earlier| earlier.key == claim.key)
+        {
+            panic!("Passport validation failed: Reserved claim '{}'", claim.key)
+        }
+    }
+
+    // Verifiers compare thumbprints against their trusted keys, so the full keys
+    // are only committed on request
+    let fingerprints = [&nma_public_key, &conflict_zone_authority_public_key]
+        .into_iter()
+        .map(|pk| jwk_thumbprint(pk).expect("Could not compute public key thumbprint"))
+        .collect();
+    let public_output = PublicOutput {
+        fingerprints,
+        pks: options
+            .commit_full_keys
+            .then(|| vec![nma_public_key, conflict_zone_authority_public_key]),
+        claims: public_claims,
     };
     env::commit(&public_output);
 }